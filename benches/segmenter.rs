@@ -0,0 +1,77 @@
+//! Benchmark the segmenter's startup and per-request latency.
+use bencher::black_box;
+use clap::Parser;
+use log::info;
+use std::error::Error;
+use std::path::PathBuf;
+use std::process;
+use std::time::{Duration, Instant};
+
+use vidyut::config::Config;
+use vidyut::segmenting::Segmenter;
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about)]
+struct Args {
+    /// Path to the Vidyut data directory
+    #[arg(short, long)]
+    data_dir: PathBuf,
+    /// The SLP1 text to segment.
+    #[arg(
+        short,
+        long,
+        default_value = "tatra BagavAn buddhaH SrAvastyAM viharati sma"
+    )]
+    text: String,
+    /// The number of times to segment `text` after warmup.
+    #[arg(short, long, default_value_t = 100)]
+    num_iterations: u32,
+    /// Ignored
+    #[arg(short, long)]
+    bench: bool,
+}
+
+fn print_duration(label: &str, dur: &Duration) {
+    println!("{label}: {} ms", dur.as_secs_f32() * 1000.0);
+}
+
+fn run(args: Args) -> Result<()> {
+    println!();
+    println!("================================");
+    println!("Segmenter");
+    println!("================================");
+
+    info!("Loading segmenter");
+    let start = Instant::now();
+    let segmenter = Segmenter::new(Config::new(&args.data_dir))?;
+    print_duration("Construction", &start.elapsed());
+
+    let start = Instant::now();
+    segmenter.warmup();
+    print_duration("Warmup", &start.elapsed());
+
+    let start = Instant::now();
+    black_box(segmenter.segment(&args.text));
+    print_duration("First segment", &start.elapsed());
+
+    let start = Instant::now();
+    for _ in 0..args.num_iterations {
+        black_box(segmenter.segment(&args.text));
+    }
+    let dur = start.elapsed() / args.num_iterations.max(1);
+    print_duration("Mean segment", &dur);
+
+    Ok(())
+}
+
+fn main() {
+    env_logger::init();
+    let args = Args::parse();
+
+    if let Err(err) = run(args) {
+        println!("{}", err);
+        process::exit(1);
+    }
+}
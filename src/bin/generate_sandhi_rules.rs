@@ -163,6 +163,17 @@ fn ru_khar(vowel: char, s: char) -> String {
     format!("{vowel}{f_result}")
 }
 
+/// Before a sibilant, visarga may optionally become that same sibilant (8.3.36 vA Sari). For
+/// example, *rAmaH sIdati* may also be written *rAmas sIdati*.
+///
+/// `ru_khar` already handles the default case where the visarga is kept.
+fn visarga_before_shar(vowel: char, s: char) -> Option<String> {
+    match s {
+        'S' | 'z' | 's' => Some(format!("{vowel}{s}")),
+        _ => None,
+    }
+}
+
 /// Sandhi for initial `as`
 fn as_sandhi(rules: &mut SandhiRules) {
     let first = "as";
@@ -182,6 +193,9 @@ fn as_sandhi(rules: &mut SandhiRules) {
             ru_khar('a', s)
         };
         rules.add(first.to_string(), s.to_string(), format!("{f_result} {s}"));
+        if let Some(f_result) = visarga_before_shar('a', s) {
+            rules.add(first.to_string(), s.to_string(), format!("{f_result} {s}"));
+        }
     }
 }
 
@@ -200,6 +214,9 @@ fn aas_sandhi(rules: &mut SandhiRules) {
             ru_khar('A', s)
         };
         rules.add(first.to_string(), s.to_string(), format!("{f_result} {s}"));
+        if let Some(f_result) = visarga_before_shar('A', s) {
+            rules.add(first.to_string(), s.to_string(), format!("{f_result} {s}"));
+        }
     }
 }
 
@@ -225,6 +242,9 @@ fn other_s_sandhi(rules: &mut SandhiRules) {
                     ru_khar(f_vowel, s)
                 };
                 rules.add(first.clone(), s.to_string(), format!("{f_result} {s}"));
+                if let Some(f_result) = visarga_before_shar(f_vowel, s) {
+                    rules.add(first.clone(), s.to_string(), format!("{f_result} {s}"));
+                }
             }
         }
     }
@@ -397,6 +417,8 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tempfile::tempdir;
+    use vidyut::sandhi::Sandhi;
 
     #[test]
     fn test_is_savarna_ac() {
@@ -407,4 +429,49 @@ mod tests {
         assert!(!is_savarna_ac('a', 'k'));
         assert!(!is_savarna_ac('a', 'i'));
     }
+
+    #[test]
+    fn test_visarga_sandhi_splits() -> Result<(), Box<dyn Error>> {
+        let mut rules = SandhiRules::new();
+        visarga_sandhi(&mut rules);
+
+        let dir = tempdir()?;
+        let config = Config::new(dir.path());
+        write_rules(rules, &config)?;
+        let sandhi = Sandhi::from_csv(config.sandhi())?;
+
+        let cases = [
+            // visarga -> S/z/s before a hard palatal, retroflex, or dental.
+            ("rAmaSca", "rAmas", "ca"),
+            ("rAmaS ca", "rAmas", "ca"),
+            ("rAmazwIkAm", "rAmas", "wIkAm"),
+            ("rAmastatra", "rAmas", "tatra"),
+            ("hariSca", "haris", "ca"),
+            ("devAstatra", "devAs", "tatra"),
+            // visarga -> H or a matching sibilant before a sibilant.
+            ("rAmaH sIdati", "rAmas", "sIdati"),
+            ("rAmas sIdati", "rAmas", "sIdati"),
+            ("rAmaSSete", "rAmas", "Sete"),
+            ("devAs sIdanti", "devAs", "sIdanti"),
+            ("haris sIdati", "haris", "sIdati"),
+            // as -> o before a voiced sound or short `a`.
+            ("rAmo gacCati", "rAmas", "gacCati"),
+            ("rAmo 'pi", "rAmas", "api"),
+            // As -> A before a voiced sound.
+            ("devA gacCanti", "devAs", "gacCanti"),
+            // other visargas -> r before a voiced sound, or lengthening before r.
+            ("harir gacCati", "haris", "gacCati"),
+            ("harI ramate", "haris", "ramate"),
+        ];
+        for (input, first, second) in cases {
+            let splits = sandhi.split_all(input);
+            assert!(
+                splits
+                    .iter()
+                    .any(|s| s.first == first && s.second == second),
+                "Could not split {input} into {first} + {second}"
+            );
+        }
+        Ok(())
+    }
 }
@@ -1,8 +1,29 @@
 //! Config options for Vidyut.
+use crate::scoring::Model;
+use std::collections::HashSet;
 use std::error::Error;
+use std::fmt;
 use std::path::{Path, PathBuf};
+use vidyut_kosha::Kosha;
+
+/// The minimum fraction of model lemmas that should also appear in the lexicon. For details, see
+/// `Config::validate`.
+const MIN_LEMMA_OVERLAP: f64 = 0.5;
+
+/// An error that occurs when building a `Config`.
+#[derive(Debug)]
+pub struct ConfigError(String);
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Error for ConfigError {}
 
 /// Stores config options and file paths for the utilities in Vidyut.
+#[derive(Clone, Debug)]
 pub struct Config {
     /// Path to a CSV of sandhi rules.
     sandhi: PathBuf,
@@ -10,17 +31,53 @@ pub struct Config {
     lexicon: PathBuf,
     /// Path to lemma counts.
     model: PathBuf,
+    /// The maximum number of sandhi splits to attempt within a single chunk.
+    max_split_depth: Option<usize>,
+    /// Whether to retry failed lexicon lookups with lowercased text.
+    lenient_case: bool,
+    /// The extra log-probability penalty per byte of text in a word that the segmenter skips.
+    unknown_word_penalty: f32,
+    /// The maximum length in bytes of a word that the segmenter looks up in the lexicon.
+    max_word_length: Option<usize>,
+    /// The maximum number of candidate solutions per remaining suffix that the segmenter keeps in
+    /// its queue.
+    beam_width: Option<usize>,
 }
 
 impl Config {
+    /// Creates a config whose data paths are all within `base_dir`.
     pub fn new(base_dir: &Path) -> Self {
         Config {
             sandhi: base_dir.join("sandhi-rules.csv"),
             lexicon: base_dir.join("lexicon"),
             model: base_dir.join("model"),
+            max_split_depth: None,
+            lenient_case: false,
+            unknown_word_penalty: 0.0,
+            max_word_length: None,
+            beam_width: None,
         }
     }
 
+    /// Caps the number of sandhi splits the segmenter attempts within a single chunk.
+    ///
+    /// Once a chunk has been split `depth` times, the segmenter emits the rest of the chunk as a
+    /// single word. This bounds the segmenter's worst-case behavior on adversarial input, such as
+    /// long runs of a repeated syllable.
+    ///
+    /// This limit is separate from `Split::is_recursive`, which rejects splits that make no
+    /// progress at all. `is_recursive` prevents infinite loops, whereas this limit prevents
+    /// finite but very deep searches.
+    pub fn with_max_split_depth(mut self, depth: usize) -> Self {
+        self.max_split_depth = Some(depth);
+        self
+    }
+
+    /// Returns a builder for a config whose data paths are set individually.
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::default()
+    }
+
     pub fn create_dirs(&self) -> Result<(), Box<dyn Error>> {
         std::fs::create_dir_all(self.lexicon())?;
         std::fs::create_dir_all(self.model())?;
@@ -39,6 +96,84 @@ impl Config {
         &self.model
     }
 
+    /// *(default: false)* Controls whether the segmenter retries failed lexicon lookups with
+    /// lowercased text.
+    ///
+    /// **Warning:** case is meaningful in SLP1, so this option can silently change the phonemes
+    /// of a word. For example, `A` is a long vowel and `K` is the aspirated `kh`, so folding `Ka`
+    /// to `ka` treats one word as an entirely different one. Enable this only for input that uses
+    /// uppercase letters by mistake, such as `CA` for `ca`.
+    ///
+    /// To limit the risk, we lowercase a word only if the original text has no lexicon entries
+    /// and the lowercased text has exactly one, so that folding never has to choose between
+    /// analyses. We also mark each folded word with `Word::is_case_folded` so that callers can
+    /// review it.
+    pub fn with_lenient_case(mut self, value: bool) -> Self {
+        self.lenient_case = value;
+        self
+    }
+
+    pub fn lenient_case(&self) -> bool {
+        self.lenient_case
+    }
+
+    /// *(default: 0.0)* Penalizes each word that the segmenter skips by `per_byte` times the
+    /// length of the word in bytes.
+    ///
+    /// The segmenter skips typos, junk, and other text it can't analyze by emitting it as a
+    /// `Pada::None` word. By default, skipping a word has a flat cost, so the segmenter may skip a
+    /// long span of valid but rare words instead of analyzing them. This penalty makes long
+    /// skips more expensive. The penalty is in the same units as the model's log-probabilities
+    /// (base 10), so a value of 1.0 makes each skipped byte ten times less likely.
+    pub fn with_unknown_word_penalty(mut self, per_byte: f32) -> Self {
+        self.unknown_word_penalty = per_byte;
+        self
+    }
+
+    pub fn unknown_word_penalty(&self) -> f32 {
+        self.unknown_word_penalty
+    }
+
+    /// Caps the length in bytes of the words that the segmenter looks up in the lexicon.
+    ///
+    /// The segmenter looks up many candidate words in each chunk, and on long runs of junk, most
+    /// of these candidates are far too long to be real words. If a candidate is longer than
+    /// `len`, the segmenter skips the lookup and treats the candidate as an unknown word. A good
+    /// value is the length of the longest word in the lexicon, which `Kosha::max_key_len` can
+    /// compute. We don't compute it by default because doing so scans the entire lexicon.
+    pub fn with_max_word_length(mut self, len: usize) -> Self {
+        self.max_word_length = Some(len);
+        self
+    }
+
+    pub fn max_word_length(&self) -> Option<usize> {
+        self.max_word_length
+    }
+
+    /// Caps the number of candidate solutions that the segmenter keeps for each remaining suffix
+    /// of the input.
+    ///
+    /// By default, the segmenter keeps every promising candidate, so its queue can grow very
+    /// large on long inputs. With a beam width, the segmenter drops its lowest-scoring candidate
+    /// for a suffix whenever it has more than `width` candidates for that suffix. Candidates for
+    /// different suffixes never compete, so the queue holds at most `width` candidates for each
+    /// suffix. This bounds memory use and speeds up the search, but the segmenter might drop a
+    /// candidate that would have led to the best solution. Without a beam width, the search is
+    /// exact. The segmenter always keeps its best candidate for each suffix, so a width of 0
+    /// behaves like a width of 1.
+    pub fn with_beam_width(mut self, width: usize) -> Self {
+        self.beam_width = Some(width);
+        self
+    }
+
+    pub fn beam_width(&self) -> Option<usize> {
+        self.beam_width
+    }
+
+    pub fn max_split_depth(&self) -> Option<usize> {
+        self.max_split_depth
+    }
+
     pub fn model_transitions(&self) -> PathBuf {
         self.model.join("transitions.csv")
     }
@@ -50,6 +185,187 @@ impl Config {
     pub fn model_lemma_counts(&self) -> PathBuf {
         self.model.join("lemma-counts.csv")
     }
+
+    /// Checks that this config's data files can be loaded and are consistent with each other.
+    ///
+    /// Specifically, we check that:
+    /// - the sandhi rules, lexicon, and model all load without errors;
+    /// - the sandhi rules and lemma counts are non-empty; and
+    /// - at least half of the model's lemmas appear in the lexicon. If not, the model was likely
+    ///   trained against a different lexicon.
+    ///
+    /// This method loads every data file, so it is about as slow as `Segmenter::new`. We recommend
+    /// calling it once at startup.
+    pub fn validate(&self) -> Result<(), Vec<ConfigError>> {
+        let mut errors = Vec::new();
+        let mut report = |msg: String| errors.push(ConfigError(msg));
+
+        match count_csv_rows(self.sandhi()) {
+            Ok(0) => report("Sandhi rules are empty".to_string()),
+            Ok(_) => (),
+            Err(e) => report(format!("Could not load sandhi rules: {e}")),
+        }
+
+        if let Err(e) = Model::new(&self.model_lemma_counts(), &self.model_transitions()) {
+            report(format!("Could not load model: {e}"));
+        }
+        let model_lemmas = match read_model_lemmas(&self.model_lemma_counts()) {
+            Ok(lemmas) if lemmas.is_empty() => {
+                report("Model lemma counts are empty".to_string());
+                lemmas
+            }
+            Ok(lemmas) => lemmas,
+            Err(_) => HashSet::new(),
+        };
+
+        match Kosha::new(self.lexicon()) {
+            Ok(lexicon) if !model_lemmas.is_empty() => {
+                let lexicon_lemmas: HashSet<String> = lexicon.lemmas().collect();
+                let num_shared = model_lemmas.intersection(&lexicon_lemmas).count();
+                let overlap = num_shared as f64 / model_lemmas.len() as f64;
+                if overlap < MIN_LEMMA_OVERLAP {
+                    report(format!(
+                        "Only {num_shared} of {} model lemmas are in the lexicon",
+                        model_lemmas.len()
+                    ));
+                }
+            }
+            Ok(_) => (),
+            Err(e) => report(format!("Could not load lexicon: {e}")),
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Returns the number of data rows in the given CSV file.
+fn count_csv_rows(path: &Path) -> Result<usize, Box<dyn Error>> {
+    let mut rdr = csv::Reader::from_path(path)?;
+    let mut count = 0;
+    for row in rdr.records() {
+        row?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Returns the set of lemmas in the given lemma counts file.
+fn read_model_lemmas(path: &Path) -> Result<HashSet<String>, Box<dyn Error>> {
+    let mut rdr = csv::Reader::from_path(path)?;
+    let mut ret = HashSet::new();
+    for row in rdr.records() {
+        ret.insert(row?[0].to_string());
+    }
+    Ok(ret)
+}
+
+/// A builder for creating a `Config` struct.
+///
+/// Use this builder if your data files are not all within a single directory:
+///
+/// ```no_run
+/// # use vidyut::config::Config;
+/// let config = Config::builder()
+///     .sandhi("/data/sandhi-rules.csv")
+///     .lexicon("/data/lexicon")
+///     .model("/models/v2")
+///     .build()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Default)]
+pub struct ConfigBuilder {
+    sandhi: Option<PathBuf>,
+    lexicon: Option<PathBuf>,
+    model: Option<PathBuf>,
+    max_split_depth: Option<usize>,
+    lenient_case: bool,
+    unknown_word_penalty: f32,
+    max_word_length: Option<usize>,
+    beam_width: Option<usize>,
+}
+
+impl ConfigBuilder {
+    /// *(required)* Sets the path to a CSV of sandhi rules.
+    pub fn sandhi(mut self, path: impl AsRef<Path>) -> Self {
+        self.sandhi = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// *(required)* Sets the path to the lexicon directory.
+    pub fn lexicon(mut self, path: impl AsRef<Path>) -> Self {
+        self.lexicon = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// *(required)* Sets the path to the model directory.
+    pub fn model(mut self, path: impl AsRef<Path>) -> Self {
+        self.model = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// *(optional)* Sets the maximum split depth. For details, see
+    /// `Config::with_max_split_depth`.
+    pub fn max_split_depth(mut self, depth: usize) -> Self {
+        self.max_split_depth = Some(depth);
+        self
+    }
+
+    /// *(optional)* Enables lenient case lookup. For details, see `Config::with_lenient_case`.
+    pub fn lenient_case(mut self, value: bool) -> Self {
+        self.lenient_case = value;
+        self
+    }
+
+    /// *(optional)* Sets the penalty for skipped words. For details, see
+    /// `Config::with_unknown_word_penalty`.
+    pub fn unknown_word_penalty(mut self, per_byte: f32) -> Self {
+        self.unknown_word_penalty = per_byte;
+        self
+    }
+
+    /// *(optional)* Sets the maximum word length. For details, see
+    /// `Config::with_max_word_length`.
+    pub fn max_word_length(mut self, len: usize) -> Self {
+        self.max_word_length = Some(len);
+        self
+    }
+
+    /// *(optional)* Sets the beam width. For details, see `Config::with_beam_width`.
+    pub fn beam_width(mut self, width: usize) -> Self {
+        self.beam_width = Some(width);
+        self
+    }
+
+    /// Creates a `Config`.
+    ///
+    /// This returns an error if a required path is missing or does not exist.
+    pub fn build(self) -> Result<Config, ConfigError> {
+        fn require(name: &str, path: Option<PathBuf>) -> Result<PathBuf, ConfigError> {
+            match path {
+                Some(p) if p.exists() => Ok(p),
+                Some(p) => Err(ConfigError(format!(
+                    "Path for `{name}` does not exist: {}",
+                    p.display()
+                ))),
+                None => Err(ConfigError(format!("Missing required path `{name}`"))),
+            }
+        }
+
+        Ok(Config {
+            sandhi: require("sandhi", self.sandhi)?,
+            lexicon: require("lexicon", self.lexicon)?,
+            model: require("model", self.model)?,
+            max_split_depth: self.max_split_depth,
+            lenient_case: self.lenient_case,
+            unknown_word_penalty: self.unknown_word_penalty,
+            max_word_length: self.max_word_length,
+            beam_width: self.beam_width,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -68,5 +384,116 @@ mod tests {
         assert!(config.model_emissions().starts_with(model_path));
         assert!(config.model_transitions().starts_with(model_path));
         assert!(config.model_lemma_counts().starts_with(model_path));
+        assert_eq!(config.max_split_depth(), None);
+        assert!(!config.lenient_case());
+    }
+
+    #[test]
+    fn test_with_max_split_depth() {
+        let config = Config::new(Path::new("/tmp")).with_max_split_depth(4);
+        assert_eq!(config.max_split_depth(), Some(4));
+    }
+
+    #[test]
+    fn test_builder() -> Result<(), Box<dyn Error>> {
+        let dir = tempfile::tempdir()?;
+        let base = dir.path();
+        Config::new(base).create_dirs()?;
+        std::fs::write(base.join("sandhi-rules.csv"), "")?;
+
+        let config = Config::builder()
+            .sandhi(base.join("sandhi-rules.csv"))
+            .lexicon(base.join("lexicon"))
+            .model(base.join("model"))
+            .max_split_depth(3)
+            .max_word_length(40)
+            .build()?;
+        assert_eq!(config.sandhi(), base.join("sandhi-rules.csv"));
+        assert_eq!(config.lexicon(), base.join("lexicon"));
+        assert_eq!(config.model(), base.join("model"));
+        assert_eq!(config.max_split_depth(), Some(3));
+        assert_eq!(config.max_word_length(), Some(40));
+        assert!(!config.lenient_case());
+        Ok(())
+    }
+
+    #[test]
+    fn test_builder_with_missing_paths() -> Result<(), Box<dyn Error>> {
+        let dir = tempfile::tempdir()?;
+        let base = dir.path();
+        Config::new(base).create_dirs()?;
+
+        // Missing path.
+        let err = Config::builder()
+            .lexicon(base.join("lexicon"))
+            .model(base.join("model"))
+            .build()
+            .unwrap_err();
+        assert!(err.to_string().contains("sandhi"));
+
+        // Path that does not exist.
+        let err = Config::builder()
+            .sandhi(base.join("sandhi-rules.csv"))
+            .lexicon(base.join("lexicon"))
+            .model(base.join("model"))
+            .build()
+            .unwrap_err();
+        assert!(err.to_string().contains("does not exist"));
+        Ok(())
+    }
+
+    /// Writes data files for a small but valid config to `base`.
+    fn write_valid_data(base: &Path, lemma_counts: &str) -> Result<Config, Box<dyn Error>> {
+        use vidyut_kosha::semantics::{Avyaya, Pada, Pratipadika};
+
+        let config = Config::new(base);
+        config.create_dirs()?;
+        std::fs::write(config.sandhi(), "first,second,result,type\na,i,e,\n")?;
+
+        let mut builder = vidyut_kosha::Builder::new(config.lexicon())?;
+        for text in ["ca", "iti"] {
+            let pada = Pada::Avyaya(Avyaya {
+                pratipadika: Pratipadika::Basic {
+                    text: text.to_string(),
+                    lingas: Vec::new(),
+                },
+            });
+            builder.insert(text, &pada)?;
+        }
+        builder.into_lexicon()?;
+
+        std::fs::write(config.model_lemma_counts(), lemma_counts)?;
+        std::fs::write(
+            config.model_transitions(),
+            "prev_state,cur_state,probability\n",
+        )?;
+        Ok(config)
+    }
+
+    #[test]
+    fn test_validate() -> Result<(), Box<dyn Error>> {
+        let dir = tempfile::tempdir()?;
+        let config = write_valid_data(dir.path(), "lemma,tag,count\nca,a,10\niti,a,5\n")?;
+        assert!(config.validate().is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_with_mismatched_lemmas() -> Result<(), Box<dyn Error>> {
+        let dir = tempfile::tempdir()?;
+        let config = write_valid_data(dir.path(), "lemma,tag,count\nca,a,10\ndeva,s,5\ngam,t,5\n")?;
+        let errors = config.validate().unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].to_string().contains("1 of 3 model lemmas"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_with_missing_files() -> Result<(), Box<dyn Error>> {
+        let dir = tempfile::tempdir()?;
+        let config = Config::new(dir.path());
+        // Sandhi rules, model, and lexicon.
+        assert_eq!(config.validate().unwrap_err().len(), 3);
+        Ok(())
     }
 }
@@ -28,13 +28,30 @@ impl Error for ConversionError {
 
 /// Convert DCS semantics to Vidyut semantics.
 pub fn standardize(t: &Token) -> Result<Word> {
-    let slp1_lemma = standardize_lemma(&t.lemma);
+    Ok(Word {
+        // The original form is not consistently present in the DCS data, so just use the lemma.
+        text: standardize_lemma(&t.lemma),
+        semantics: parse_pada(t)?,
+        is_case_folded: false,
+        boundary: None,
+    })
+}
 
-    let semantics = match t.upos.as_str() {
+/// Converts the morphological tags of a DCS token to a Vidyut `Pada`.
+///
+/// This is the same mapping that `standardize` uses. It returns `None` if the token has a part
+/// of speech or a tag value that we don't know how to map.
+pub fn to_pada(t: &Token) -> Option<Pada> {
+    parse_pada(t).ok()
+}
+
+/// Reshapes the semantics of a DCS token into a Vidyut pada.
+fn parse_pada(t: &Token) -> Result<Pada> {
+    let pada = match t.upos.as_str() {
         "NOUN" | "PRON" | "ADJ" | "NUM" => parse_subanta(t)?,
         "CONJ" | "CCONJ" | "SCONJ" | "ADV" | "PART" | "INTJ" | "ADP" => Pada::Avyaya(Avyaya {
             pratipadika: Pratipadika::Basic {
-                text: slp1_lemma.clone(),
+                text: standardize_lemma(&t.lemma),
                 lingas: Vec::new(),
             },
         }),
@@ -48,14 +65,9 @@ pub fn standardize(t: &Token) -> Result<Word> {
             }
         }
         "MANTRA" => Pada::None,
-        _ => panic!("Unknown upos `{}`", t.upos),
+        _ => return Err(ConversionError::new(&t.upos)),
     };
-
-    Ok(Word {
-        // The original form is not consistently present in the DCS data, so just use the lemma.
-        text: slp1_lemma,
-        semantics,
-    })
+    Ok(pada)
 }
 
 /// Standardizes the DCS lemma against Vidyut's conventions.
@@ -310,3 +322,64 @@ fn parse_verb_pada(_f: &TokenFeatures) -> PadaPrayoga {
     // FIXME: unsupported in DCS?
     PadaPrayoga::None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conllu::Reader;
+
+    /// Parses the tokens of a single CoNLL-U sentence.
+    fn tokens(conllu: &str) -> Vec<Token> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.conllu");
+        std::fs::write(&path, conllu).unwrap();
+        let mut reader = Reader::from_path(&path).unwrap();
+        reader.next().unwrap().tokens
+    }
+
+    #[test]
+    fn test_to_pada() {
+        let tokens = tokens(
+            "# text = rāmo gacchati\n\
+             1\trāmaḥ\trāma\tNOUN\t_\tCase=Nom|Gender=Masc|Number=Sing\n\
+             2\tgacchati\tgam\tVERB\t_\tMood=Ind|Number=Sing|Person=3|Tense=Pres\n\
+             3\tca\tca\tCCONJ\t_\t_\n\
+             4\tx\tx\tPUNCT\t_\t_\n",
+        );
+
+        assert_eq!(
+            to_pada(&tokens[0]),
+            Some(Pada::Subanta(Subanta {
+                pratipadika: Pratipadika::Basic {
+                    text: "rAma".to_string(),
+                    lingas: Vec::new(),
+                },
+                linga: Linga::Pum,
+                vacana: Vacana::Eka,
+                vibhakti: Vibhakti::V1,
+                is_purvapada: false,
+            }))
+        );
+        assert_eq!(
+            to_pada(&tokens[1]),
+            Some(Pada::Tinanta(Tinanta {
+                dhatu: Dhatu("gam".to_string()),
+                purusha: Purusha::Prathama,
+                vacana: Vacana::Eka,
+                lakara: Lakara::Lat,
+                pada: PadaPrayoga::None,
+            }))
+        );
+        assert_eq!(
+            to_pada(&tokens[2]),
+            Some(Pada::Avyaya(Avyaya {
+                pratipadika: Pratipadika::Basic {
+                    text: "ca".to_string(),
+                    lingas: Vec::new(),
+                },
+            }))
+        );
+        // Unknown part of speech.
+        assert_eq!(to_pada(&tokens[3]), None);
+    }
+}
@@ -14,3 +14,6 @@ pub mod translit;
 
 mod normalize_text;
 mod strict_mode;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
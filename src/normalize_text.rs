@@ -4,12 +4,25 @@ use regex::Regex;
 /// Creates a normalized version of `text` that is easier to process.
 ///
 /// We normalize as follows:
-/// 1. Separate all input into three kinds of spans: text, whitespace, and symbols.
+/// 1. Separate all input into four kinds of spans: text, whitespace, punctuation, and other
+///    symbols.
 /// 2. Delete all whitespace spans.
 /// 3. Separate all remaining spans with a single " ".
+///
+/// Since punctuation has its own spans, the segmenter returns each run of punctuation as a
+/// separate token, e.g. `12,` becomes `12` and `,`.
+///
+/// A `3` that directly follows a vowel is a pluta marker (as in `o3m`) and is kept as part of the
+/// text span.
 pub fn normalize(text: &str) -> String {
     lazy_static! {
-        static ref RE: Regex = Regex::new(r"([a-zA-Z']+)|(\s+)|([^a-zA-Z']+)").unwrap();
+        static ref RE: Regex = Regex::new(concat!(
+            r"((?:[aAiIuUfFxXeEoO]3|[a-zA-Z'])+)",
+            r"|(\s+)",
+            r#"|([.,;:!?|()\[\]{}"।॥]+)"#,
+            r#"|([^a-zA-Z'\s.,;:!?|()\[\]{}"।॥]+)"#,
+        ))
+        .unwrap();
     }
 
     let mut ret = RE
@@ -57,6 +70,25 @@ mod tests {
             ("deva--iti", "deva -- iti"),
             (":deva12345iti!", ": deva 12345 iti !"),
             (":deva  12345iti!", ": deva 12345 iti !"),
+            ("deva12 -- iti", "deva 12 -- iti"),
+            ("deva 12  --  iti", "deva 12 -- iti"),
+            ("deva12, iti", "deva 12 , iti"),
+            ("(deva) iti।", "( deva ) iti ।"),
+            ("deva || 1 ||", "deva || 1 ||"),
+        ];
+        for (input, output) in cases {
+            assert_eq!(normalize(input), output.to_string());
+        }
+    }
+
+    #[test]
+    fn test_pluta() {
+        let cases = vec![
+            ("o3m", "o3m"),
+            ("o3m iti", "o3m iti"),
+            ("agne3 iti", "agne3 iti"),
+            // A `3` that doesn't follow a vowel is not a pluta marker.
+            ("om3", "om 3"),
         ];
         for (input, output) in cases {
             assert_eq!(normalize(input), output.to_string());
@@ -6,8 +6,11 @@ use lazy_static::lazy_static;
 use multimap::MultiMap;
 use regex::Regex;
 use std::cmp;
+use std::collections::HashSet;
 use std::error::Error;
+use std::io;
 use std::path::Path;
+use std::sync::OnceLock;
 
 /// Maps a combination to the two strings (first, second) that created it.
 pub type SandhiMap = MultiMap<String, (String, String)>;
@@ -47,8 +50,24 @@ impl Split {
     }
 }
 
+/// A sandhi rule that combines the end of one word with the start of the next.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SandhiRule {
+    /// The final sounds of the first word.
+    pub first: String,
+    /// The initial sounds of the second word. If empty, the rule applies only at the end of the
+    /// input.
+    pub second: String,
+    /// The combined result, which may contain a space between the two words.
+    pub result: String,
+    /// Whether some other rule combines the same `first` and `second` differently.
+    pub is_optional: bool,
+}
+
 pub struct Sandhi {
     map: MultiMap<String, (String, String)>,
+    /// The rules in `map`, which we create on first use since most callers never need them.
+    rules: OnceLock<Vec<SandhiRule>>,
     len_longest_key: usize,
 }
 
@@ -61,19 +80,40 @@ impl Sandhi {
             .expect("Sandhi map is empty");
         Self {
             map,
+            rules: OnceLock::new(),
             len_longest_key,
         }
     }
 
+    /// Iterates over all of the rules in this map, sorted by `first`, `second`, and `result`.
+    ///
+    /// `from_csv` also adds a copy of each rule whose result has no spaces, so that we can split
+    /// text that was written without them. Since these copies are not separate rules, we don't
+    /// include them here.
+    ///
+    /// We create this list on the first call, so callers that never list rules pay nothing for it.
+    pub fn rules(&self) -> impl Iterator<Item = &SandhiRule> {
+        self.rules.get_or_init(|| create_rules(&self.map)).iter()
+    }
+
     /// Creates a map from sandhi combinations to the sounds that created them.
     ///
     /// # Arguments
     ///
     /// - `path` - C TSV with columns `first`, `second`, `result`, and `type`.
     pub fn from_csv(path: &Path) -> Result<Self, Box<dyn Error>> {
+        Self::from_reader(std::fs::File::open(path)?)
+    }
+
+    /// Creates a map from sandhi combinations by reading CSV data from `reader`.
+    ///
+    /// The data should have the same format as the file passed to `from_csv`. Use this method to
+    /// load rules from a source other than a local file, such as a decompressed stream or a
+    /// network response.
+    pub fn from_reader(reader: impl io::Read) -> Result<Self, Box<dyn Error>> {
         let mut rules = SandhiMap::new();
 
-        let mut rdr = csv::Reader::from_path(path)?;
+        let mut rdr = csv::Reader::from_reader(reader);
         for maybe_row in rdr.records() {
             let row = maybe_row?;
             let first = String::from(&row[0]);
@@ -112,7 +152,7 @@ impl Sandhi {
             second: input[i + 1..].trim_start().to_string(),
             // We are at the end of a chunk if and only if the next sound is not a Sanskrit sound
             // (or and avagraha).
-            is_end_of_chunk: !&input[i + 1..].starts_with(sounds::is_sanskrit),
+            is_end_of_chunk: !sounds::is_sanskrit_at(input, i + 1),
             kind: SplitKind::Prefix,
         });
 
@@ -147,6 +187,10 @@ impl Sandhi {
         }
 
         for j in i..cmp::min(input.len(), i + self.len_longest_key + 1) {
+            // `input` might contain non-ASCII punctuation, such as a Devanagari danda.
+            if !input.is_char_boundary(j) {
+                continue;
+            }
             let combination = &input[i..j];
             let is_end_of_chunk = combination.contains(' ');
 
@@ -172,18 +216,116 @@ impl Sandhi {
         res
     }
 
+    /// Returns all ways to join `first` and `second` according to our sandhi rules.
+    ///
+    /// We consider only the rules that apply at the boundary between `first` and `second`, i.e.
+    /// rules whose first part ends `first` and whose second part starts `second`. Results are
+    /// sorted and deduplicated. If no rule applies, the result is empty.
+    pub fn apply_at(&self, first: &str, second: &str) -> Vec<String> {
+        let mut res = Vec::new();
+        for (combination, pairs) in self.map.iter_all() {
+            for (f, s) in pairs {
+                // Rules with an empty second part apply only at the end of the input.
+                let applies_to_second = if s.is_empty() {
+                    second.is_empty()
+                } else {
+                    second.starts_with(s.as_str())
+                };
+                if first.ends_with(f.as_str()) && applies_to_second {
+                    let prefix = &first[..first.len() - f.len()];
+                    let suffix = &second[s.len()..];
+                    res.push(String::from(prefix) + combination + suffix);
+                }
+            }
+        }
+        res.sort();
+        res.dedup();
+        res
+    }
+
     /// Temporary function until we migrate to split_at everywhere.
     pub fn split_all(&self, input: &str) -> Vec<Split> {
         let mut splits = Vec::new();
         for i in 0..input.len() {
             // Break on non-sounds so that `first` is a continuous chunk.
-            if !input[i..].starts_with(sounds::is_sanskrit) {
+            if !sounds::is_sanskrit_at(input, i) {
                 break;
             }
             splits.extend(self.split_at(input, i));
         }
         splits
     }
+
+    /// Runs `split_all` on each of the given words.
+    ///
+    /// Several rules can undo to the same split, so we also remove any split whose `first` and
+    /// `second` match an earlier split for the same word.
+    pub fn split_all_batch(&self, words: &[&str]) -> Vec<Vec<Split>> {
+        words
+            .iter()
+            .map(|word| {
+                let mut seen = HashSet::new();
+                self.split_all(word)
+                    .into_iter()
+                    .filter(|s| seen.insert((s.first.clone(), s.second.clone())))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Creates the list of rules that produced `map`.
+fn create_rules(map: &SandhiMap) -> Vec<SandhiRule> {
+    // `from_csv` adds a spaceless copy of each rule whose result has spaces. Find these copies so
+    // that we can skip them below.
+    let spaceless: Vec<(&str, &str, String)> = map
+        .iter_all()
+        .filter(|(result, _)| result.contains(' '))
+        .flat_map(|(result, pairs)| {
+            pairs.iter().map(move |(first, second)| {
+                (first.as_str(), second.as_str(), result.replace(' ', ""))
+            })
+        })
+        .collect();
+    let spaceless: HashSet<(&str, &str, &str)> = spaceless
+        .iter()
+        .map(|(first, second, result)| (*first, *second, result.as_str()))
+        .collect();
+
+    let mut rules: Vec<SandhiRule> = Vec::new();
+    for (result, pairs) in map.iter_all() {
+        for (first, second) in pairs {
+            if spaceless.contains(&(first.as_str(), second.as_str(), result.as_str())) {
+                continue;
+            }
+            rules.push(SandhiRule {
+                first: first.clone(),
+                second: second.clone(),
+                result: result.clone(),
+                is_optional: false,
+            });
+        }
+    }
+
+    rules.sort_by(|x, y| (&x.first, &x.second, &x.result).cmp(&(&y.first, &y.second, &y.result)));
+    rules.dedup();
+
+    // Since `rules` is sorted, rules with the same `first` and `second` are adjacent.
+    let mut start = 0;
+    while start < rules.len() {
+        let mut end = start + 1;
+        while end < rules.len()
+            && rules[end].first == rules[start].first
+            && rules[end].second == rules[start].second
+        {
+            end += 1;
+        }
+        for rule in &mut rules[start..end] {
+            rule.is_optional = end - start > 1;
+        }
+        start = end;
+    }
+    rules
 }
 
 /// Hackily converts a word ending with a visarga to end with an `s`.
@@ -206,7 +348,7 @@ fn is_good_first(text: &str) -> bool {
         // Must not end with a double consonant (exceptions: yrlv).
         static ref RE_DOUBLE_HAL: Regex = Regex::new(
             // non-yaN + hal
-            r"[kKgGNcCjJYwWqQRtTdDnpPbBmzSsh][kKgGNcCjJYwWqQRtTdDnpPbBmyrlvzSsh]$").unwrap();
+            r"[kKgGNcCjJYwWqQRtTdDnpPbBmzSshL][kKgGNcCjJYwWqQRtTdDnpPbBmyrlvzSshL]$").unwrap();
     }
 
     if RE_DOUBLE_AC.is_match(text) || RE_DOUBLE_HAL.is_match(text) {
@@ -268,6 +410,7 @@ mod tests {
         ];
         let sandhi = Sandhi {
             map: rules,
+            rules: OnceLock::new(),
             len_longest_key: 2,
         };
 
@@ -292,6 +435,7 @@ mod tests {
         ];
         let sandhi = Sandhi {
             map: rules,
+            rules: OnceLock::new(),
             len_longest_key: 1,
         };
 
@@ -313,6 +457,7 @@ mod tests {
         ];
         let sandhi = Sandhi {
             map: rules,
+            rules: OnceLock::new(),
             len_longest_key: 1,
         };
 
@@ -328,6 +473,7 @@ mod tests {
         ];
         let sandhi = Sandhi {
             map: rules,
+            rules: OnceLock::new(),
             len_longest_key: 1,
         };
 
@@ -348,7 +494,7 @@ mod tests {
         ] {
             assert!(is_good_first(word));
         }
-        for word in &["PalaM", "zaz", "vAc"] {
+        for word in &["PalaM", "zaz", "vAc", "ILL"] {
             assert!(!is_good_first(word));
         }
     }
@@ -371,4 +517,108 @@ mod tests {
             assert!(!is_good_second(word), "failed: {}", word);
         }
     }
+
+    #[test]
+    fn test_from_csv_matches_from_reader() -> Result<(), Box<dyn Error>> {
+        let csv = "first,second,result,type\n\
+            a,i,e,general\n\
+            as,c,aS c,general\n\
+            s,,H,general\n";
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("sandhi-rules.csv");
+        std::fs::write(&path, csv)?;
+
+        let from_path = Sandhi::from_csv(&path)?;
+        let from_reader = Sandhi::from_reader(csv.as_bytes())?;
+        for input in ["ceti", "rAmaS ca", "devaH"] {
+            for i in 0..input.len() {
+                assert_eq!(from_path.split_at(input, i), from_reader.split_at(input, i));
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_rules() {
+        let csv = "first,second,result,type\n\
+            a,i,e,general\n\
+            t,h,d D,general\n\
+            t,h,t h,general\n\
+            s,,H,general\n\
+            a,a,A,internal\n";
+        let sandhi = Sandhi::from_reader(csv.as_bytes()).unwrap();
+        let rule = |first: &str, second: &str, result: &str, is_optional| SandhiRule {
+            first: first.to_string(),
+            second: second.to_string(),
+            result: result.to_string(),
+            is_optional,
+        };
+
+        // We create the rules only when they're first requested.
+        assert!(sandhi.rules.get().is_none());
+        let rules: Vec<_> = sandhi.rules().cloned().collect();
+        assert!(sandhi.rules.get().is_some());
+        assert_eq!(
+            rules,
+            vec![
+                rule("a", "i", "e", false),
+                rule("s", "", "H", false),
+                rule("t", "h", "d D", true),
+                rule("t", "h", "t h", true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_apply_at() {
+        let csv = "first,second,result,type\n\
+            a,i,e,general\n\
+            aH,i,a i,general\n\
+            t,c,c c,general\n\
+            s,,H,general\n";
+        let sandhi = Sandhi::from_reader(csv.as_bytes()).unwrap();
+
+        assert_eq!(sandhi.apply_at("rAma", "iti"), vec!["rAmeti"]);
+        assert_eq!(sandhi.apply_at("devaH", "iha"), vec!["deva iha", "devaiha"]);
+        assert_eq!(sandhi.apply_at("tat", "ca"), vec!["tac ca", "tacca"]);
+        assert_eq!(sandhi.apply_at("devas", ""), vec!["devaH"]);
+
+        // Rules for the end of the input don't apply before another word.
+        assert!(sandhi.apply_at("devas", "iha").is_empty());
+        // No rule applies.
+        assert!(sandhi.apply_at("tat", "iti").is_empty());
+    }
+
+    #[test]
+    fn test_split_all_batch() {
+        // The same rule appears twice.
+        let csv = "first,second,result,type\n\
+            a,i,e,general\n\
+            a,i,e,general\n\
+            s,,H,general\n";
+        let sandhi = Sandhi::from_reader(csv.as_bytes()).unwrap();
+        let pairs = |splits: &[Split]| -> Vec<(String, String)> {
+            splits
+                .iter()
+                .map(|s| (s.first.clone(), s.second.clone()))
+                .collect()
+        };
+        let caiti = ("ca".to_string(), "iti".to_string());
+        let all = pairs(&sandhi.split_all("ceti"));
+        assert_eq!(all.iter().filter(|p| **p == caiti).count(), 2);
+
+        let batch = sandhi.split_all_batch(&["ceti", "devaH"]);
+        assert_eq!(batch.len(), 2);
+        for splits in &batch {
+            let pairs = pairs(splits);
+            let mut deduped = pairs.clone();
+            deduped.sort();
+            deduped.dedup();
+            assert_eq!(pairs.len(), deduped.len());
+        }
+        assert_eq!(pairs(&batch[0]).len(), all.len() - 1);
+        assert!(pairs(&batch[0]).contains(&caiti));
+        assert!(pairs(&batch[1]).contains(&("devas".to_string(), "".to_string())));
+        assert!(sandhi.split_all_batch(&[]).is_empty());
+    }
 }
@@ -5,8 +5,11 @@ use core::str::FromStr;
 use modular_bitfield::prelude::*;
 use std::collections::HashMap;
 use std::error::Error;
+use std::fs::File;
+use std::io;
 use std::num::ParseIntError;
 use std::path::Path;
+use std::thread;
 use vidyut_kosha::semantics::POSTag;
 use vidyut_kosha::semantics::*;
 
@@ -133,9 +136,13 @@ struct LemmaModel {
 
 impl LemmaModel {
     fn new(path: &Path) -> Result<Self, Box<dyn Error>> {
+        Self::from_reader(File::open(path)?)
+    }
+
+    fn from_reader(reader: impl io::Read) -> Result<Self, Box<dyn Error>> {
         let mut counts = HashMap::new();
 
-        let mut rdr = csv::Reader::from_path(path)?;
+        let mut rdr = csv::Reader::from_reader(reader);
         for maybe_row in rdr.records() {
             let r = maybe_row?;
             let lemma = &r[0];
@@ -188,10 +195,14 @@ struct TransitionModel {
 
 impl TransitionModel {
     fn new(path: &Path) -> Result<Self, Box<dyn Error>> {
+        Self::from_reader(File::open(path)?)
+    }
+
+    fn from_reader(reader: impl io::Read) -> Result<Self, Box<dyn Error>> {
         type Key = (State, State);
 
         let mut log_probs = HashMap::new();
-        let mut rdr = csv::Reader::from_path(path)?;
+        let mut rdr = csv::Reader::from_reader(reader);
         for maybe_row in rdr.records() {
             let row = maybe_row?;
 
@@ -217,19 +228,55 @@ impl TransitionModel {
     }
 }
 
+/// The parts of a single word's score. For details, see `Model::score`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ScoreComponents {
+    /// The log probability of the word's lemma and part of speech.
+    pub lemma_log_prob: f32,
+    /// The log probability of moving from the previous word's state to this word's state.
+    pub transition_log_prob: f32,
+    /// Any penalty subtracted from the word's score, in the same units as `total`.
+    pub penalty: i32,
+}
+
+impl ScoreComponents {
+    /// Returns how much this word adds to the phrase score, in the units used by `Model::score`.
+    pub fn total(&self) -> i32 {
+        (100_f32 * (self.lemma_log_prob + self.transition_log_prob)) as i32 - self.penalty
+    }
+}
+
 pub struct Model {
     lemmas: LemmaModel,
     transitions: TransitionModel,
 }
 
 impl Model {
+    /// Loads a model from the given files.
+    ///
+    /// The two files are independent, so we read them concurrently.
     pub fn new(lemma_counts_path: &Path, transitions_path: &Path) -> Result<Self, Box<dyn Error>> {
-        let lemmas = LemmaModel::new(lemma_counts_path)?;
-        let transitions = TransitionModel::new(transitions_path)?;
+        // `Box<dyn Error>` isn't `Send`, so pass errors between threads as strings.
+        let (lemmas, transitions) = thread::scope(|s| {
+            let lemmas = s.spawn(|| LemmaModel::new(lemma_counts_path).map_err(|e| e.to_string()));
+            let transitions = TransitionModel::new(transitions_path).map_err(|e| e.to_string());
+            let lemmas = lemmas.join().expect("lemma loading thread panicked");
+            (lemmas, transitions)
+        });
 
         Ok(Model {
-            lemmas,
-            transitions,
+            lemmas: lemmas?,
+            transitions: transitions?,
+        })
+    }
+
+    /// Creates a model from in-memory CSV data.
+    ///
+    /// Each argument should have the same contents as the corresponding file passed to `new`.
+    pub fn from_bytes(lemma_counts: &[u8], transitions: &[u8]) -> Result<Self, Box<dyn Error>> {
+        Ok(Model {
+            lemmas: LemmaModel::from_reader(lemma_counts)?,
+            transitions: TransitionModel::from_reader(transitions)?,
         })
     }
 
@@ -241,28 +288,38 @@ impl Model {
     pub fn score(&self, phrase: &Phrase) -> i32 {
         let n = phrase.words.len();
         let delta = if let Some(last) = phrase.words.last() {
-            let prev_state = if n >= 2 {
-                State::from_pada(&phrase.words[n - 2].semantics)
+            let prev = if n >= 2 {
+                Some(&phrase.words[n - 2].semantics)
             } else {
-                State::initial()
+                None
             };
-
-            let cur_state = State::from_pada(&last.semantics);
-
-            let pada = &last.semantics;
-            let lemma_log_prob = self
-                .lemmas
-                .log_prob(pada.lemma(), pada.part_of_speech_tag());
-            let transition_log_prob = self.transitions.log_prob(&prev_state, &cur_state);
-            lemma_log_prob + transition_log_prob
+            self.score_components(prev, &last.semantics).total()
         } else {
-            0.0
+            0
         };
 
         // To simplify the scoring, assume that:
         //
         //     P(W[0], ..., W[n]) = P(W[0], ..., W[n-1]) * P(W[n] | W[0], ..., W[n-1])
-        phrase.score + (100_f32 * delta) as i32
+        phrase.score + delta
+    }
+
+    /// Returns the parts of the score for a word with semantics `cur` that follows a word with
+    /// semantics `prev`, or that starts the phrase if `prev` is `None`.
+    ///
+    /// The model applies no penalties of its own, so `penalty` is always 0.
+    pub fn score_components(&self, prev: Option<&Pada>, cur: &Pada) -> ScoreComponents {
+        let prev_state = match prev {
+            Some(p) => State::from_pada(p),
+            None => State::initial(),
+        };
+        let cur_state = State::from_pada(cur);
+
+        ScoreComponents {
+            lemma_log_prob: self.lemmas.log_prob(cur.lemma(), cur.part_of_speech_tag()),
+            transition_log_prob: self.transitions.log_prob(&prev_state, &cur_state),
+            penalty: 0,
+        }
     }
 }
 
@@ -276,4 +333,33 @@ mod tests {
         assert_eq!(log_prob(10.0, 100), -1.0);
         assert_eq!(log_prob(10.0, 1000), -2.0);
     }
+
+    #[test]
+    fn test_new_matches_from_bytes() -> Result<(), Box<dyn Error>> {
+        let lemma_counts = "lemma,tag,count\ndeva,s,10\ngam,t,5\nca,a,20\n";
+        let transitions = "prev_state,cur_state,probability\n0,0,0.5\n";
+
+        let dir = tempfile::tempdir()?;
+        let lemma_counts_path = dir.path().join("lemma-counts.csv");
+        let transitions_path = dir.path().join("transitions.csv");
+        std::fs::write(&lemma_counts_path, lemma_counts)?;
+        std::fs::write(&transitions_path, transitions)?;
+
+        let concurrent = Model::new(&lemma_counts_path, &transitions_path)?;
+        let sequential = Model::from_bytes(lemma_counts.as_bytes(), transitions.as_bytes())?;
+        assert_eq!(concurrent.lemmas.log_probs, sequential.lemmas.log_probs);
+        assert_eq!(
+            concurrent.lemmas.log_p_unknown,
+            sequential.lemmas.log_p_unknown
+        );
+        assert!(concurrent.transitions.log_probs == sequential.transitions.log_probs);
+        Ok(())
+    }
+
+    #[test]
+    fn test_new_with_missing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing.csv");
+        assert!(Model::new(&missing, &missing).is_err());
+    }
 }
@@ -1,24 +1,83 @@
 //! Segments Sanskrit phrases into separate words with their morphological analysis.
 use log::{debug, log_enabled, Level};
-use priority_queue::PriorityQueue;
+use priority_queue::DoublePriorityQueue;
+use std::cmp;
 use std::collections::HashMap;
 use std::error::Error;
+use std::ops::Range;
 
 use crate::config::Config;
 use crate::normalize_text::normalize;
 use crate::sandhi;
 use crate::sandhi::Sandhi;
-use crate::scoring::Model;
+use crate::scoring::{Model, ScoreComponents};
 use crate::sounds;
 use crate::strict_mode;
 use vidyut_kosha::semantics::Pada;
 use vidyut_kosha::Kosha;
 
+/// Describes how a word was separated from the word before it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Boundary {
+    /// The words were separated by a space, and no sandhi rule was undone.
+    Space,
+    /// The words were written together with no space, and no sandhi rule was undone.
+    Join,
+    /// The words were separated by undoing a sandhi rule, e.g. `rAmaS ca` -> `rAmas` + `ca`.
+    Sandhi,
+}
+
+impl Boundary {
+    /// Returns the boundary that `split` creates between `split.first` and the next word.
+    fn from_split(split: &sandhi::Split) -> Self {
+        match split.kind {
+            sandhi::SplitKind::Standard => Boundary::Sandhi,
+            sandhi::SplitKind::Prefix if split.is_end_of_chunk => Boundary::Space,
+            sandhi::SplitKind::Prefix => Boundary::Join,
+        }
+    }
+}
+
 /// Represnts a Sanskrit word and its semantics.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Word {
     pub text: String,
     pub semantics: Pada,
+    /// Whether we found this word only by lowercasing the input text. For details, see
+    /// `Config::with_lenient_case`.
+    pub is_case_folded: bool,
+    /// How this word was separated from the previous word, or `None` if this is the first word.
+    pub boundary: Option<Boundary>,
+}
+
+/// Serializes a word as an object with the fields `text`, `lemma`, `pos`, `is_case_folded`, and
+/// `boundary`. Here, `lemma` and `pos` summarize `semantics`, and `pos` is a short tag such as
+/// `s` for *subanta*.
+///
+/// Bindings for other languages, such as our WebAssembly bindings, should use this representation
+/// so that they stay in sync with `Word`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Word {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        // Destructure so that adding a field to `Word` breaks the build until we serialize it.
+        let Word {
+            text,
+            semantics,
+            is_case_folded,
+            boundary,
+        } = self;
+        let mut state = serializer.serialize_struct("Word", 5)?;
+        state.serialize_field("text", text)?;
+        state.serialize_field("lemma", &semantics.lemma())?;
+        state.serialize_field("pos", semantics.part_of_speech_tag().as_str())?;
+        state.serialize_field("is_case_folded", is_case_folded)?;
+        state.serialize_field("boundary", boundary)?;
+        state.end()
+    }
 }
 
 impl Word {
@@ -26,6 +85,63 @@ impl Word {
     pub fn lemma(&self) -> String {
         self.semantics.lemma()
     }
+
+    /// Returns a compact, human-readable summary of the word and its semantics.
+    ///
+    /// The format is `{text}: {analysis}`, where `analysis` depends on the kind of word:
+    ///
+    /// | Word          | Format                                   | Example                   |
+    /// |---------------|------------------------------------------|---------------------------|
+    /// | *tiṅanta*     | `√{dhatu}, {lakara}, {purusha}{vacana}`  | `gacCati: √gam, lat, 3s`  |
+    /// | *subanta*     | `{stem}, {linga}, {vibhakti}{vacana}`    | `rAmas: rAma, m, 1s`      |
+    /// | *avyaya*      | `{stem}, avyaya`                         | `ca: ca, avyaya`          |
+    /// | unknown       | `?`                                      | `kaH: ?`                  |
+    ///
+    /// Each field uses the short form returned by its `as_str` method, and a *subanta* that is
+    /// the non-final member of a compound ends with `, purvapada`. We won't change this format
+    /// without a version bump.
+    pub fn gloss(&self) -> String {
+        let analysis = match &self.semantics {
+            Pada::Tinanta(t) => format!(
+                "√{}, {}, {}{}",
+                t.dhatu.0,
+                t.lakara.as_str(),
+                t.purusha.as_str(),
+                t.vacana.as_str()
+            ),
+            Pada::Subanta(s) => {
+                let mut ret = format!(
+                    "{}, {}, {}{}",
+                    s.pratipadika.lemma(),
+                    s.linga.as_str(),
+                    s.vibhakti.as_str(),
+                    s.vacana.as_str()
+                );
+                if s.is_purvapada {
+                    ret += ", purvapada";
+                }
+                ret
+            }
+            Pada::Avyaya(a) => format!("{}, avyaya", a.pratipadika.lemma()),
+            Pada::None => "?".to_string(),
+        };
+        format!("{}: {}", self.text, analysis)
+    }
+
+    /// Returns whether this word is a *daṇḍa*, which marks the end of a sentence or verse.
+    ///
+    /// SLP1 texts usually write a *daṇḍa* as `.` or `|` and a double *daṇḍa* as `..` or `||`. We
+    /// also accept the Devanagari `।` and `॥`. Since the segmenter returns each run of punctuation
+    /// as a separate word, callers can use this method to split the output into sentences or
+    /// verses.
+    pub fn is_danda(&self) -> bool {
+        self.semantics == Pada::None
+            && !self.text.is_empty()
+            && self
+                .text
+                .chars()
+                .all(|c| matches!(c, '.' | '|' | '।' | '॥'))
+    }
 }
 
 /// Represents an in-progress segment of a phrase.
@@ -37,6 +153,12 @@ pub struct Phrase {
     pub remaining: String,
     /// The score associated with this in-progress solution.
     pub score: i32,
+    /// The number of sandhi splits made since the start of the current chunk.
+    pub chunk_depth: usize,
+    /// For each word in `words`, the index of the input chunk that the word starts in.
+    pub word_chunks: Vec<usize>,
+    /// The boundary between the last word in `words` and the next word we add.
+    pub next_boundary: Boundary,
 }
 
 impl Phrase {
@@ -47,10 +169,131 @@ impl Phrase {
             remaining: text,
             // log_10(1) = 0
             score: 0,
+            chunk_depth: 0,
+            word_chunks: Vec::new(),
+            next_boundary: Boundary::Space,
+        }
+    }
+
+    /// Adds a word that starts in the same chunk as `self.remaining`.
+    ///
+    /// `next_boundary` is the boundary between this word and the word that follows it.
+    fn push_word(&mut self, mut word: Word, num_chunks: usize, next_boundary: Boundary) {
+        word.boundary = if self.words.is_empty() {
+            None
+        } else {
+            Some(self.next_boundary)
+        };
+        self.next_boundary = next_boundary;
+        self.word_chunks
+            .push(num_chunks - count_chunks(&self.remaining));
+        self.words.push(word);
+    }
+}
+
+/// Returns the number of space-separated chunks in some normalized text.
+fn count_chunks(text: &str) -> usize {
+    if text.is_empty() {
+        0
+    } else {
+        text.matches(' ').count() + 1
+    }
+}
+
+/// The number of unchanged chunks on either side of an edit that `Segmenter::resegment` will
+/// segment again.
+const RESEGMENT_CONTEXT_CHUNKS: usize = 1;
+
+/// A segmented text that remembers which input chunk each word came from.
+///
+/// Here, a *chunk* is a span of the normalized input text that contains no spaces. We use chunks
+/// to re-segment only part of a text after it has been edited. For details, see
+/// `Segmenter::resegment`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Segmentation {
+    /// The normalized input text.
+    text: String,
+    /// The words we found in `text`.
+    words: Vec<Word>,
+    /// For each word in `words`, the index of the chunk that the word starts in.
+    word_chunks: Vec<usize>,
+}
+
+impl Segmentation {
+    /// The normalized text that was segmented. Byte offsets passed to `Segmenter::resegment`
+    /// refer to this text.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The words we found in the text.
+    pub fn words(&self) -> &[Word] {
+        &self.words
+    }
+
+    /// Returns whether sandhi left chunks `i` and `i + 1` unchanged, i.e. whether chunk `i` ends
+    /// with its last word and chunk `i + 1` starts with its first word.
+    ///
+    /// We can segment the text on either side of a clean boundary independently.
+    fn is_clean_boundary(&self, i: usize) -> bool {
+        let chunk_words = |k: usize| {
+            self.words
+                .iter()
+                .zip(&self.word_chunks)
+                .filter(move |(_, &c)| c == k)
+                .map(|(w, _)| w.text.as_str())
+        };
+        let mut chunks = self.text.split(' ').skip(i);
+        match (
+            chunks.next(),
+            chunks.next(),
+            chunk_words(i).next_back(),
+            chunk_words(i + 1).next(),
+        ) {
+            (Some(left), Some(right), Some(last), Some(first)) => {
+                left.ends_with(last) && right.starts_with(first)
+            }
+            _ => false,
         }
     }
 }
 
+/// A word in the best segmentation along with the analyses that it beat. For details, see
+/// `Segmenter::segment_with_alternatives`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WordWithAlternatives {
+    /// The chosen word.
+    pub word: Word,
+    /// The model's score for the chosen word.
+    pub score: i32,
+    /// Other analyses of the same text and their scores, from highest to lowest score.
+    pub alternatives: Vec<(Pada, i32)>,
+}
+
+/// Statistics about a single run of the segmenter's search.
+///
+/// These counts are useful for performance tuning, e.g. for finding inputs that are unusually
+/// expensive to segment.
+///
+/// The segmenter's caches are local to a single call and are dropped when the call returns, so
+/// memory use does not grow across calls to the same `Segmenter`. The cache sizes here describe
+/// the peak memory use of one call.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    /// The number of states popped from the priority queue.
+    pub num_pops: usize,
+    /// The number of states pushed onto the priority queue.
+    pub num_pushes: usize,
+    /// The number of word lookups that were served by the word cache.
+    pub num_cache_hits: usize,
+    /// The number of entries in the word cache when the search finished.
+    pub num_word_cache_entries: usize,
+    /// The number of entries in the viterbi cache when the search finished.
+    pub num_viterbi_entries: usize,
+    /// The largest number of states in the priority queue at any point in the search.
+    pub max_queue_len: usize,
+}
+
 /// A Sanskrit segmenter.
 pub struct Segmenter {
     /// Sandhi rules. The segmenter uses these rules to exhaustively split a Sanskrit expression
@@ -62,46 +305,737 @@ pub struct Segmenter {
     /// A scoring model. The segmenter uses this model to score candidate solutions and prioritize
     /// solutions that are the most promising.
     model: Model,
+    /// If set, the maximum number of sandhi splits to attempt within a single chunk.
+    max_split_depth: Option<usize>,
+    /// If set, retry failed lexicon lookups with lowercased text.
+    lenient_case: bool,
+    /// The extra log-probability penalty per byte of text in a `Pada::None` word.
+    unknown_word_penalty: f32,
+    /// If set, the maximum length of a word to look up in the lexicon.
+    max_word_length: Option<usize>,
+    /// If set, the maximum number of states to keep in the search queue for each remaining suffix.
+    beam_width: Option<usize>,
+    /// Lexicons to consult after `lexicon`. For details, see `Segmenter::with_lexicon`.
+    extra_lexicons: Vec<Box<dyn Lexicon>>,
+}
+
+/// A source of analyses for Sanskrit words.
+///
+/// The segmenter uses a `Kosha` as its main lexicon, but it can also consult other lexicons. For
+/// details, see `Segmenter::with_lexicon`.
+pub trait Lexicon {
+    /// Returns all analyses of the word `key`, or an empty `Vec` if `key` is not a known word.
+    fn lookup(&self, key: &str) -> Result<Vec<Pada>, Box<dyn Error>>;
+}
+
+impl Lexicon for Kosha {
+    fn lookup(&self, key: &str) -> Result<Vec<Pada>, Box<dyn Error>> {
+        self.get_all(key).iter().map(|p| self.unpack(p)).collect()
+    }
 }
 
+impl Lexicon for HashMap<String, Vec<Pada>> {
+    fn lookup(&self, key: &str) -> Result<Vec<Pada>, Box<dyn Error>> {
+        Ok(self.get(key).cloned().unwrap_or_default())
+    }
+}
+
+/// A short phrase that exercises the main code paths of the segmenter.
+const WARMUP_TEXT: &str = "rAmo vanaM gacCati iti || 1 ||";
+
 impl Segmenter {
     /// Creates a segmenter from the given input data.
+    ///
+    /// This reads the sandhi rules, lexicon, and model into memory, which is expensive for the
+    /// full data set. So we recommend creating a segmenter once and reusing it. To also move the
+    /// cost of the first call to `segment` to startup, see `warmup`.
     pub fn new(config: Config) -> Result<Self, Box<dyn Error>> {
         Ok(Segmenter {
             sandhi: Sandhi::from_csv(config.sandhi()).expect("Could not read sandhi rules."),
             lexicon: Kosha::new(config.lexicon()).expect("Could not read lexicon."),
             model: Model::new(&config.model_lemma_counts(), &config.model_transitions())?,
+            max_split_depth: config.max_split_depth(),
+            lenient_case: config.lenient_case(),
+            unknown_word_penalty: config.unknown_word_penalty(),
+            max_word_length: config.max_word_length(),
+            beam_width: config.beam_width(),
+            extra_lexicons: Vec::new(),
         })
     }
 
+    /// Creates a segmenter from data that has already been loaded.
+    ///
+    /// Use this constructor in environments without a filesystem, such as WebAssembly. For
+    /// example, `Kosha::from_bytes` loads a lexicon from memory.
+    pub fn from_data(sandhi: Sandhi, lexicon: Kosha, model: Model) -> Self {
+        Segmenter {
+            sandhi,
+            lexicon,
+            model,
+            max_split_depth: None,
+            lenient_case: false,
+            unknown_word_penalty: 0.0,
+            max_word_length: None,
+            beam_width: None,
+            extra_lexicons: Vec::new(),
+        }
+    }
+
+    /// Adds a lexicon for the segmenter to consult in addition to its main lexicon.
+    ///
+    /// Use this method to add a supplementary word list, such as a list of domain-specific terms,
+    /// without rebuilding the main lexicon. The segmenter consults its main lexicon first and
+    /// then each extra lexicon in the order it was added, and it considers the analyses from all
+    /// of them.
+    pub fn with_lexicon(mut self, lexicon: impl Lexicon + 'static) -> Self {
+        self.extra_lexicons.push(Box::new(lexicon));
+        self
+    }
+
     pub fn lexicon(&self) -> &Kosha {
         &self.lexicon
     }
 
+    /// Prepares the segmenter for its first real request.
+    ///
+    /// Some of the segmenter's setup is deferred until first use. For example, we compile
+    /// several regular expressions lazily. `warmup` does this work ahead of time by segmenting a
+    /// short sample phrase, so that the first real call to `segment` is about as fast as later
+    /// ones. Servers should call `warmup` once at startup.
+    pub fn warmup(&self) {
+        self.segment(WARMUP_TEXT);
+    }
+
     /// Segments the given text.
     ///
     /// `raw_text` should be an SLP1 string.
     pub fn segment(&self, raw_text: &str) -> Vec<Word> {
-        segment(raw_text, self).expect("Is OK")
+        self.segment_incremental(raw_text).words
+    }
+
+    /// Segments the given text and returns the normalized text along with its words.
+    ///
+    /// `segment` normalizes its input before segmenting it, e.g. by collapsing whitespace. Use
+    /// this method to see the text that the segmenter actually worked with, such as when
+    /// debugging why a word was not found.
+    ///
+    /// `raw_text` should be an SLP1 string.
+    pub fn segment_verbose(&self, raw_text: &str) -> (String, Vec<Word>) {
+        let Segmentation { text, words, .. } = self.segment_incremental(raw_text);
+        (text, words)
+    }
+
+    /// Segments the given text and returns the lemma of each word we recognized.
+    ///
+    /// Words with unknown semantics are skipped. Lemmas are returned in the order that they
+    /// appear in the text and are not deduplicated.
+    ///
+    /// `raw_text` should be an SLP1 string.
+    pub fn lemmas(&self, raw_text: &str) -> Vec<String> {
+        self.segment(raw_text)
+            .iter()
+            .filter(|w| w.semantics != Pada::None)
+            .map(|w| w.lemma())
+            .collect()
+    }
+
+    /// Segments the given text and returns every analysis of each word in the best segmentation.
+    ///
+    /// `segment` picks a single `Pada` for each word. But a surface form often has several valid
+    /// analyses (e.g. `te` as a form of both `tad` and `yuzmad`), and this method returns all of
+    /// them in lexicon order. Words that aren't in the lexicon have the single analysis
+    /// `Pada::None`.
+    ///
+    /// `raw_text` should be an SLP1 string.
+    pub fn segment_ambiguous(&self, raw_text: &str) -> Vec<(String, Vec<Pada>)> {
+        self.segment(raw_text)
+            .into_iter()
+            .map(|w| {
+                let padas = self.analyses(&w);
+                (w.text, padas)
+            })
+            .collect()
+    }
+
+    /// Segments the given text and ranks the alternative analyses of each word.
+    ///
+    /// Each word's alternatives are the analyses returned by `segment_ambiguous` other than the
+    /// chosen one. We score each alternative as if it had been chosen in place of the word, given
+    /// the word before it, and sort alternatives from highest to lowest score. Scores use the same
+    /// scale as the segmenter's internal search, so they are comparable with `score`.
+    ///
+    /// `raw_text` should be an SLP1 string.
+    pub fn segment_with_alternatives(&self, raw_text: &str) -> Vec<WordWithAlternatives> {
+        let words = self.segment(raw_text);
+        let mut ret: Vec<WordWithAlternatives> = Vec::with_capacity(words.len());
+        for (i, word) in words.iter().enumerate() {
+            let prev = if i > 0 { Some(&words[i - 1]) } else { None };
+            let mut alternatives: Vec<(Pada, i32)> = self
+                .analyses(word)
+                .into_iter()
+                .filter(|p| *p != word.semantics)
+                .map(|p| {
+                    let score = self.word_score(prev, &word.text, &p);
+                    (p, score)
+                })
+                .collect();
+            alternatives.sort_by_key(|x| cmp::Reverse(x.1));
+
+            ret.push(WordWithAlternatives {
+                word: word.clone(),
+                score: self.word_score(prev, &word.text, &word.semantics),
+                alternatives,
+            });
+        }
+        ret
+    }
+
+    /// Returns every analysis of `word` in the lexicon, or just its own semantics if the lexicon
+    /// has none.
+    fn analyses(&self, word: &Word) -> Vec<Pada> {
+        let key = if word.is_case_folded {
+            word.text.to_lowercase()
+        } else {
+            word.text.clone()
+        };
+        let mut padas = self.lookup(&key).unwrap_or_default();
+        if padas.is_empty() {
+            padas.push(word.semantics.clone());
+        }
+        padas
+    }
+
+    /// Returns all analyses of `key` from our main lexicon and any extra lexicons, in that order.
+    fn lookup(&self, key: &str) -> Result<Vec<Pada>, Box<dyn Error>> {
+        let mut ret = self.lexicon.lookup(key)?;
+        for lexicon in &self.extra_lexicons {
+            for pada in lexicon.lookup(key)? {
+                if !ret.contains(&pada) {
+                    ret.push(pada);
+                }
+            }
+        }
+        Ok(ret)
+    }
+
+    /// Returns the score for a word with the given `text` and `semantics` that follows `prev`.
+    fn word_score(&self, prev: Option<&Word>, text: &str, semantics: &Pada) -> i32 {
+        let mut phrase = Phrase::new(String::new());
+        phrase.words.extend(prev.cloned());
+        phrase.words.push(Word {
+            text: text.to_string(),
+            semantics: semantics.clone(),
+            is_case_folded: false,
+            boundary: None,
+        });
+        self.score(&phrase)
+    }
+
+    /// Scores the given phrase with our model and applies `unknown_word_penalty` to its last
+    /// word.
+    fn score(&self, phrase: &Phrase) -> i32 {
+        let score = self.model.score(phrase);
+        match phrase.words.last() {
+            Some(w) => score - self.penalty(w),
+            None => score,
+        }
+    }
+
+    /// Returns the `unknown_word_penalty` for `word`, or 0 if `word` is known.
+    fn penalty(&self, word: &Word) -> i32 {
+        if word.semantics == Pada::None {
+            (100_f32 * self.unknown_word_penalty * word.text.len() as f32) as i32
+        } else {
+            0
+        }
+    }
+
+    /// Returns the parts of each word's score in the given segmentation.
+    ///
+    /// The `total` of each item is how much that word adds to the score of the full
+    /// segmentation, so these items explain why the model prefers one segmentation over another.
+    pub fn score_breakdown(&self, words: &[Word]) -> Vec<ScoreComponents> {
+        words
+            .iter()
+            .enumerate()
+            .map(|(i, word)| {
+                let prev = i.checked_sub(1).map(|j| &words[j].semantics);
+                let mut components = self.model.score_components(prev, &word.semantics);
+                components.penalty = self.penalty(word);
+                components
+            })
+            .collect()
+    }
+
+    /// Segments the given text and returns a result that can be cheaply updated with
+    /// `resegment`.
+    ///
+    /// `raw_text` should be an SLP1 string.
+    pub fn segment_incremental(&self, raw_text: &str) -> Segmentation {
+        let mut stats = SearchStats::default();
+        segment(&normalize(raw_text), self, &mut stats).expect("Is OK")
+    }
+
+    /// Segments the start of the given text and returns at most `max_words` words.
+    ///
+    /// This method is an approximation of `segment` that is much faster on long inputs. We
+    /// segment only a window of chunks at the start of the text, and we grow the window until
+    /// the words we return are separated from the rest of the window by a clean chunk boundary
+    /// followed by `RESEGMENT_CONTEXT_CHUNKS` chunks of lookahead. Since the model scores each
+    /// word based on the word before it, text after the window can still change the result in
+    /// rare cases. But in practice, the words we return almost always match the first
+    /// `max_words` words of `segment`.
+    ///
+    /// `raw_text` should be an SLP1 string.
+    pub fn segment_prefix(&self, raw_text: &str, max_words: usize) -> Vec<Word> {
+        if max_words == 0 {
+            return Vec::new();
+        }
+
+        let text = normalize(raw_text);
+        let chunks: Vec<&str> = text.split(' ').collect();
+        let mut window = cmp::min(max_words + RESEGMENT_CONTEXT_CHUNKS, chunks.len());
+        loop {
+            let mut stats = SearchStats::default();
+            let mut seg = segment(&chunks[..window].join(" "), self, &mut stats).expect("Is OK");
+
+            let is_done = window == chunks.len()
+                || (seg.words.len() > max_words && {
+                    // The chunk after the last word we return.
+                    let lo = seg.word_chunks[max_words - 1] + 1;
+                    let hi = window.saturating_sub(RESEGMENT_CONTEXT_CHUNKS);
+                    (lo..=hi).any(|b| seg.is_clean_boundary(b - 1))
+                });
+            if is_done {
+                seg.words.truncate(max_words);
+                return seg.words;
+            }
+            window = cmp::min(2 * window, chunks.len());
+        }
+    }
+
+    /// Segments the given text without normalizing it first.
+    ///
+    /// `segment` normalizes its input before searching, e.g. by collapsing runs of whitespace and
+    /// splitting punctuation into separate tokens. This method skips that step, which is useful
+    /// for debugging and for pipelines that have already normalized their text. Callers are then
+    /// responsible for providing clean SLP1: tokens separated by single spaces, with no leading
+    /// or trailing whitespace.
+    pub fn segment_raw(&self, text: &str) -> Vec<Word> {
+        let mut stats = SearchStats::default();
+        segment(text, self, &mut stats).expect("Is OK").words
+    }
+
+    /// Updates a previous segmentation after replacing the bytes in `edit` with `replacement`.
+    ///
+    /// `edit` is a byte range in `prev.text()`. Rather than segmenting the entire edited text, we
+    /// segment only the chunks that the edit touches along with at least
+    /// `RESEGMENT_CONTEXT_CHUNKS` chunks on either side. We extend this window until each of its
+    /// ends is a chunk boundary that sandhi left unchanged, and we reuse the words in `prev` for
+    /// all chunks outside of the window.
+    ///
+    /// This usually gives the same result as segmenting the full text. But the result might
+    /// differ if the best segmentation of a chunk outside the window depends on the edited text,
+    /// e.g. through the scoring model's transition probabilities. If you need an exact result,
+    /// use `segment` instead.
+    ///
+    /// If `edit` is not a valid range in `prev.text()`, i.e. if it is out of bounds, reversed, or
+    /// not on a char boundary, we ignore the edit and return a copy of `prev`.
+    pub fn resegment(
+        &self,
+        prev: &Segmentation,
+        edit: Range<usize>,
+        replacement: &str,
+    ) -> Segmentation {
+        let old_text = &prev.text;
+        if old_text.get(edit.clone()).is_none() {
+            return prev.clone();
+        }
+        let new_text =
+            normalize(&(old_text[..edit.start].to_string() + replacement + &old_text[edit.end..]));
+        if old_text.is_empty() {
+            return self.segment_incremental(&new_text);
+        }
+        let old_chunks: Vec<&str> = old_text.split(' ').collect();
+        let new_chunks: Vec<&str> = new_text.split(' ').collect();
+
+        // The range of old chunks to segment again, inclusive.
+        let mut lo = old_text[..edit.start]
+            .matches(' ')
+            .count()
+            .saturating_sub(RESEGMENT_CONTEXT_CHUNKS);
+        while lo > 0 && !prev.is_clean_boundary(lo - 1) {
+            lo -= 1;
+        }
+        let mut old_hi = cmp::min(
+            old_text[..edit.end].matches(' ').count() + RESEGMENT_CONTEXT_CHUNKS,
+            old_chunks.len() - 1,
+        );
+        while old_hi + 1 < old_chunks.len() && !prev.is_clean_boundary(old_hi) {
+            old_hi += 1;
+        }
+        let num_after = old_chunks.len() - 1 - old_hi;
+
+        // Normalization might merge or split chunks outside of our window, in which case we fall
+        // back to segmenting the full text.
+        let is_aligned = new_chunks.len() > lo + num_after
+            && old_chunks[..lo] == new_chunks[..lo]
+            && old_chunks[old_hi + 1..] == new_chunks[new_chunks.len() - num_after..];
+        if !is_aligned {
+            return self.segment_incremental(&new_text);
+        }
+
+        // The end of the window in `new_chunks`, exclusive.
+        let new_hi = new_chunks.len() - num_after;
+        let window = self.segment_incremental(&new_chunks[lo..new_hi].join(" "));
+
+        let mut words = Vec::new();
+        let mut word_chunks = Vec::new();
+        for (word, &chunk) in prev.words.iter().zip(&prev.word_chunks) {
+            if chunk < lo {
+                words.push(word.clone());
+                word_chunks.push(chunk);
+            }
+        }
+        for (word, &chunk) in window.words.iter().zip(&window.word_chunks) {
+            let mut word = word.clone();
+            if word.boundary.is_none() && !words.is_empty() {
+                // The window starts at a clean boundary, so sandhi did not apply there.
+                word.boundary = Some(Boundary::Space);
+            }
+            words.push(word);
+            word_chunks.push(lo + chunk);
+        }
+        for (word, &chunk) in prev.words.iter().zip(&prev.word_chunks) {
+            if chunk > old_hi {
+                words.push(word.clone());
+                word_chunks.push(chunk - old_hi - 1 + new_hi);
+            }
+        }
+
+        Segmentation {
+            text: new_text,
+            words,
+            word_chunks,
+        }
+    }
+
+    /// Joins the given words into continuous text by applying sandhi at each word boundary.
+    ///
+    /// This is roughly the inverse of `segment`. Where our sandhi rules allow several results, we
+    /// prefer the result with the most spaces, i.e. one that keeps a space between the two words
+    /// (e.g. `tac ca` over `tacca`, or `vane 'tra` over `vanayatra`). Among results with the same
+    /// number of spaces, we use the first in sorted order. Where no rule applies, we join the
+    /// words with a space.
+    pub fn join(&self, words: &[Word]) -> String {
+        // `apply_at` returns its results in sorted order, and `min_by_key` returns the first of
+        // several equal results.
+        let pick = |results: Vec<String>| {
+            results
+                .into_iter()
+                .min_by_key(|r| cmp::Reverse(r.matches(' ').count()))
+        };
+
+        let mut ret = String::new();
+        for word in words.iter().filter(|w| !w.text.is_empty()) {
+            if ret.is_empty() {
+                ret = word.text.clone();
+            } else {
+                ret = match pick(self.sandhi.apply_at(&ret, &word.text)) {
+                    Some(joined) => joined,
+                    None => ret + " " + &word.text,
+                };
+            }
+        }
+        // Apply any rules for the end of the text, e.g. a final `s` becomes a visarga.
+        match pick(self.sandhi.apply_at(&ret, "")) {
+            Some(joined) => joined,
+            None => ret,
+        }
+    }
+
+    /// Segments the given text and returns statistics about the search instead of its result.
+    ///
+    /// `raw_text` should be an SLP1 string.
+    pub fn segment_stats(&self, raw_text: &str) -> SearchStats {
+        let mut stats = SearchStats::default();
+        segment(&normalize(raw_text), self, &mut stats).expect("Is OK");
+        stats
+    }
+}
+
+/// Merges each run of adjacent `Pada::None` words into a single word.
+///
+/// The segmenter emits `Pada::None` for typos, numbers, and other non-Sanskrit content, and a
+/// single run of junk often becomes several such words. Since the segmenter emits `Pada::None`
+/// only for a complete chunk of normalized text, we join the merged words with a single space.
+///
+/// We never merge a *daṇḍa* (see `Word::is_danda`) so that sentence and verse boundaries survive.
+pub fn merge_unknown_words(words: Vec<Word>) -> Vec<Word> {
+    let mut ret: Vec<Word> = Vec::with_capacity(words.len());
+    for word in words {
+        if let Some(prev) = ret.last_mut() {
+            if prev.semantics == Pada::None
+                && word.semantics == Pada::None
+                && !prev.is_danda()
+                && !word.is_danda()
+            {
+                prev.text.push(' ');
+                prev.text.push_str(&word.text);
+                continue;
+            }
+        }
+        ret.push(word);
+    }
+    ret
+}
+
+/// Joins `words` into display text, with `sep` between the members of each compound.
+///
+/// The segmenter emits each member of a compound as its own word, and each non-final member is a
+/// *subanta* with `is_purvapada` set. So we join a *purvapada* to the word after it with `sep`
+/// and join all other words with a single space. For example, with `sep = "-"`, the words
+/// `rAja`, `puruzas`, and `gacCati` become `rAja-puruzas gacCati`.
+pub fn display_compounds(words: &[Word], sep: &str) -> String {
+    let mut ret = String::new();
+    let mut prev_is_purvapada = false;
+    for (i, word) in words.iter().enumerate() {
+        if i > 0 {
+            ret += if prev_is_purvapada { sep } else { " " };
+        }
+        ret += &word.text;
+        prev_is_purvapada = matches!(&word.semantics, Pada::Subanta(s) if s.is_purvapada);
+    }
+    ret
+}
+
+/// The maximum number of texts to list in `AgreementReport::most_divergent`.
+const MAX_DIVERGENT_TEXTS: usize = 10;
+
+/// How much two segmenters agree on a single text. For details, see `agreement`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextAgreement {
+    /// The input text.
+    pub text: String,
+    /// The fraction of words that both segmenters found.
+    pub word_agreement: f64,
+    /// The fraction of words that both segmenters found with the same analysis.
+    pub analysis_agreement: f64,
+}
+
+/// How much two segmenters agree on a corpus. For details, see `agreement`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AgreementReport {
+    /// The number of words in the corpus. For each text, we count the words from whichever
+    /// segmenter found more of them.
+    pub num_words: usize,
+    /// The number of words that both segmenters found.
+    pub num_shared_words: usize,
+    /// The number of words that both segmenters found with the same analysis.
+    pub num_shared_analyses: usize,
+    /// The texts with the lowest analysis agreement, starting with the lowest. We list at most
+    /// `MAX_DIVERGENT_TEXTS` texts and skip texts where the segmenters fully agree.
+    pub most_divergent: Vec<TextAgreement>,
+}
+
+impl AgreementReport {
+    /// Returns the fraction of words that both segmenters found.
+    pub fn word_agreement(&self) -> f64 {
+        fraction(self.num_shared_words, self.num_words)
+    }
+
+    /// Returns the fraction of words that both segmenters found with the same analysis.
+    pub fn analysis_agreement(&self) -> f64 {
+        fraction(self.num_shared_analyses, self.num_words)
+    }
+}
+
+/// Returns `num / denom`, or 1.0 if `denom` is 0.
+fn fraction(num: usize, denom: usize) -> f64 {
+    if denom == 0 {
+        1.0
+    } else {
+        num as f64 / denom as f64
+    }
+}
+
+/// Segments each of `texts` with both `a` and `b` and reports how much their results agree.
+///
+/// We align the two segmentations of each text with `diff_segmentations`. A word is *shared* if
+/// it aligns with a word in the other segmentation, and its *analysis* is shared if the aligned
+/// words also have the same semantics. Use this function to check how much a config change
+/// affects the segmenter's output before evaluating it against a gold standard.
+pub fn agreement(a: &Segmenter, b: &Segmenter, texts: &[&str]) -> AgreementReport {
+    let mut num_words = 0;
+    let mut num_shared_words = 0;
+    let mut num_shared_analyses = 0;
+    let mut divergent = Vec::new();
+
+    for text in texts {
+        let words_a = a.segment(text);
+        let words_b = b.segment(text);
+        let n = cmp::max(words_a.len(), words_b.len());
+        let (mut shared_words, mut shared_analyses) = (0, 0);
+        for diff in diff_segmentations(&words_a, &words_b) {
+            match diff {
+                SegDiff::Same(_) => {
+                    shared_words += 1;
+                    shared_analyses += 1;
+                }
+                SegDiff::Changed { .. } => shared_words += 1,
+                _ => (),
+            }
+        }
+
+        num_words += n;
+        num_shared_words += shared_words;
+        num_shared_analyses += shared_analyses;
+        if shared_analyses < n {
+            divergent.push(TextAgreement {
+                text: text.to_string(),
+                word_agreement: fraction(shared_words, n),
+                analysis_agreement: fraction(shared_analyses, n),
+            });
+        }
     }
+
+    divergent.sort_by(|x, y| x.analysis_agreement.total_cmp(&y.analysis_agreement));
+    divergent.truncate(MAX_DIVERGENT_TEXTS);
+    AgreementReport {
+        num_words,
+        num_shared_words,
+        num_shared_analyses,
+        most_divergent: divergent,
+    }
+}
+
+/// A sign that a segmentation might be wrong. For details, see `check_segmentation`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SegWarning {
+    /// The word at `index` is identical to the word just after it, in both text and semantics.
+    RepeatedWord { index: usize },
+}
+
+/// Checks a segmentation for patterns that often indicate a bad parse.
+///
+/// For now, we flag only adjacent duplicate words. Garbled input can cause the segmenter to emit
+/// the same word twice in a row, and while Sanskrit does repeat words (e.g. *punaH punaH*), a
+/// repetition is rare enough to be worth a second look. These warnings are heuristics, so we
+/// leave `words` unchanged.
+pub fn check_segmentation(words: &[Word]) -> Vec<SegWarning> {
+    words
+        .windows(2)
+        .enumerate()
+        .filter(|(_, w)| w[0].text == w[1].text && w[0].semantics == w[1].semantics)
+        .map(|(index, _)| SegWarning::RepeatedWord { index })
+        .collect()
+}
+
+/// One step in the difference between two segmentations. For details, see
+/// `diff_segmentations`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SegDiff {
+    /// Both segmentations have this word with the same analysis.
+    Same(Word),
+    /// Both segmentations have a word with this text, but their analyses differ.
+    Changed { old: Word, new: Word },
+    /// Only the second segmentation has these words.
+    Inserted(Vec<Word>),
+    /// Only the first segmentation has these words.
+    Deleted(Vec<Word>),
+    /// The first segmentation's words were replaced by the second's, e.g. when a word is split
+    /// into two or two words are merged into one.
+    Replaced { old: Vec<Word>, new: Vec<Word> },
+}
+
+/// Compares two segmentations of the same text and returns the differences between them.
+///
+/// We align words by their text with a longest common subsequence. Aligned words become `Same`
+/// or `Changed`, and each run of unaligned words between them becomes `Inserted`, `Deleted`, or
+/// `Replaced`. So if a single word is split into two, the rest of the alignment is unaffected.
+pub fn diff_segmentations(a: &[Word], b: &[Word]) -> Vec<SegDiff> {
+    // lcs[i][j] is the length of the longest common subsequence of `a[i..]` and `b[j..]`.
+    let mut lcs = vec![vec![0_usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i].text == b[j].text {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                cmp::max(lcs[i + 1][j], lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut ret = Vec::new();
+    let mut old = Vec::new();
+    let mut new = Vec::new();
+    let flush = |ret: &mut Vec<SegDiff>, old: &mut Vec<Word>, new: &mut Vec<Word>| {
+        let old = std::mem::take(old);
+        let new = std::mem::take(new);
+        match (old.is_empty(), new.is_empty()) {
+            (true, true) => (),
+            (true, false) => ret.push(SegDiff::Inserted(new)),
+            (false, true) => ret.push(SegDiff::Deleted(old)),
+            (false, false) => ret.push(SegDiff::Replaced { old, new }),
+        }
+    };
+
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i].text == b[j].text {
+            flush(&mut ret, &mut old, &mut new);
+            if a[i].semantics == b[j].semantics {
+                ret.push(SegDiff::Same(a[i].clone()));
+            } else {
+                ret.push(SegDiff::Changed {
+                    old: a[i].clone(),
+                    new: b[j].clone(),
+                });
+            }
+            i += 1;
+            j += 1;
+        } else if j == b.len() || (i < a.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            old.push(a[i].clone());
+            i += 1;
+        } else {
+            new.push(b[j].clone());
+            j += 1;
+        }
+    }
+    flush(&mut ret, &mut old, &mut new);
+    ret
 }
 
 // FIXME: better as an iterator, but hard to implement. For now, update statefully then iterate in
 // caller.
+//
+// If `segmenter` has lenient case lookup enabled and we find `text` only after lowercasing it, we
+// also record the lowercased text in `folded`. We fold only if the lowercased text is unambiguous,
+// i.e. has exactly one analysis.
 fn analyze_pada(
     text: &str,
     split: &sandhi::Split,
     segmenter: &Segmenter,
     cache: &mut HashMap<String, Vec<Pada>>,
+    folded: &mut HashMap<String, String>,
 ) -> Result<(), Box<dyn Error>> {
     if !cache.contains_key(text) {
-        let res: Result<Vec<Pada>, _> = segmenter
-            .lexicon
-            .get_all(text)
-            .iter()
-            .map(|p| segmenter.lexicon.unpack(p))
-            .collect();
-        let mut res = res?;
+        let lookup = |key: &str| match segmenter.max_word_length {
+            Some(n) if key.len() > n => Ok(Vec::new()),
+            _ => segmenter.lookup(key),
+        };
+        let mut res = lookup(text)?;
+
+        if res.is_empty() && segmenter.lenient_case {
+            let lower = text.to_lowercase();
+            if lower != text {
+                let folded_res = lookup(&lower)?;
+                if folded_res.len() == 1 {
+                    res = folded_res;
+                    folded.insert(text.to_string(), lower);
+                }
+            }
+        }
 
         // Add the option to skip an entire chunk. (For typos, junk, etc.)
         if split.is_end_of_chunk || text.starts_with(|c| !sounds::is_sanskrit(c)) {
@@ -124,7 +1058,7 @@ fn debug_print_phrase(p: &Phrase) {
 }
 
 #[allow(dead_code)]
-fn debug_print_stack(pq: &PriorityQueue<Phrase, i32>) {
+fn debug_print_stack(pq: &DoublePriorityQueue<Phrase, i32>) {
     if log_enabled!(Level::Debug) {
         debug!("Stack:");
 
@@ -154,32 +1088,99 @@ fn debug_print_viterbi(v: &HashMap<String, HashMap<String, Phrase>>) {
     }
 }
 
+/// The states in the search queue, grouped by the text they have remaining.
+///
+/// beam[remainder] = the queued states that have $remainder text remaining in the input.
+type Beam = HashMap<String, Vec<(Phrase, i32)>>;
+
+/// Pushes `state` onto the search queue. If `beam_width` is set, we then drop the lowest-scoring
+/// queued state with the same remaining text until at most `beam_width` such states are queued.
+///
+/// The beam is a limit per remaining suffix, which is the same key that the viterbi cache uses. So
+/// states that cover different amounts of the input never compete with each other.
+fn push_state(
+    pq: &mut DoublePriorityQueue<Phrase, i32>,
+    beam: &mut Beam,
+    state: Phrase,
+    score: i32,
+    beam_width: Option<usize>,
+    stats: &mut SearchStats,
+) {
+    stats.num_pushes += 1;
+    match beam_width {
+        None => {
+            pq.push(state, score);
+        }
+        Some(width) => {
+            let remaining = state.remaining.clone();
+            // If `state` is already queued, it's already in the beam too.
+            if pq.push(state.clone(), score).is_none() {
+                let bucket = beam.entry(remaining).or_default();
+                bucket.push((state, score));
+                // Always keep at least one state per suffix so that the search can make progress.
+                if bucket.len() > cmp::max(width, 1) {
+                    let worst = (0..bucket.len()).min_by_key(|&i| bucket[i].1).unwrap();
+                    let (worst, _) = bucket.swap_remove(worst);
+                    pq.remove(&worst);
+                }
+            }
+        }
+    }
+    stats.max_queue_len = cmp::max(stats.max_queue_len, pq.len());
+}
+
+/// Pops the best state from the search queue and removes it from `beam`.
+fn pop_state(pq: &mut DoublePriorityQueue<Phrase, i32>, beam: &mut Beam) -> Option<(Phrase, i32)> {
+    let (state, score) = pq.pop_max()?;
+    if let Some(bucket) = beam.get_mut(&state.remaining) {
+        if let Some(i) = bucket.iter().position(|(s, _)| *s == state) {
+            bucket.swap_remove(i);
+        }
+    }
+    Some((state, score))
+}
+
 /// Segments the given text.
 ///
 /// # Arguments:
-/// - `raw_text` - a text string in SLP1.
+/// - `text` - a normalized text string in SLP1.
 ///
 /// The segmenter makes a best effort to understand the input as valid Sanskrit text, even if it
 /// contains typos or other content that is not valid Sanskrit.
-fn segment(raw_text: &str, ctx: &Segmenter) -> Result<Vec<Word>, Box<dyn Error>> {
-    let text = normalize(raw_text);
-    let mut pq = PriorityQueue::new();
+fn segment(
+    text: &str,
+    ctx: &Segmenter,
+    stats: &mut SearchStats,
+) -> Result<Segmentation, Box<dyn Error>> {
+    let text = text.to_string();
+    let num_chunks = count_chunks(&text);
+    let mut pq = DoublePriorityQueue::new();
+    let mut beam = Beam::new();
     let mut word_cache: HashMap<String, Vec<Pada>> = HashMap::new();
+    let mut folded_cache: HashMap<String, String> = HashMap::new();
 
     // viterbi_cache[remainder][state] = the best result that ends with $state and has $remainder
     // text remaining in the input.
     let mut viterbi_cache: HashMap<String, HashMap<String, Phrase>> = HashMap::new();
 
-    let initial_state = Phrase::new(text);
+    let initial_state = Phrase::new(text.clone());
     let score = initial_state.score;
-    pq.push(initial_state, score);
+    push_state(
+        &mut pq,
+        &mut beam,
+        initial_state,
+        score,
+        ctx.beam_width,
+        stats,
+    );
 
     while !pq.is_empty() {
         debug_print_stack(&pq);
         // debug_print_viterbi(&viterbi_cache);
 
         // Pop the best solution remaining.
-        let (cur, cur_score) = pq.pop().unwrap();
+        let (cur, cur_score) = pop_state(&mut pq, &mut beam).unwrap();
+        stats.num_pops += 1;
 
         // The best solution remaining is complete, so we can stop here.
         //
@@ -209,53 +1210,87 @@ fn segment(raw_text: &str, ctx: &Segmenter) -> Result<Vec<Word>, Box<dyn Error>>
                 Some((first, second)) => {
                     let mut new = Phrase {
                         words: cur.words.clone(),
-                        remaining: second.to_string(),
+                        remaining: cur.remaining.clone(),
                         // HACK: this is buggy -- scoring based on cur score set here?
                         score: cur_score,
+                        chunk_depth: 0,
+                        word_chunks: cur.word_chunks.clone(),
+                        next_boundary: cur.next_boundary,
                     };
-                    new.words.push(Word {
-                        text: first.to_string(),
-                        semantics: Pada::None,
-                    });
+                    new.push_word(
+                        Word {
+                            text: first.to_string(),
+                            semantics: Pada::None,
+                            is_case_folded: false,
+                            boundary: None,
+                        },
+                        num_chunks,
+                        Boundary::Space,
+                    );
+                    new.remaining = second.to_string();
                     new
                 }
                 None => {
                     let mut new = Phrase {
                         words: cur.words.clone(),
-                        remaining: "".to_string(),
+                        remaining: cur.remaining.clone(),
                         // HACK: this is buggy -- scoring based on cur score set here?
                         score: cur_score,
+                        chunk_depth: 0,
+                        word_chunks: cur.word_chunks.clone(),
+                        next_boundary: cur.next_boundary,
                     };
-                    new.words.push(Word {
-                        text: cur.remaining.to_string(),
-                        semantics: Pada::None,
-                    });
+                    new.push_word(
+                        Word {
+                            text: cur.remaining.to_string(),
+                            semantics: Pada::None,
+                            is_case_folded: false,
+                            boundary: None,
+                        },
+                        num_chunks,
+                        Boundary::Space,
+                    );
+                    new.remaining = "".to_string();
                     new
                 }
             };
 
-            new.score = ctx.model.score(&new);
+            new.score = ctx.score(&new);
             viterbi_cache
                 .entry(new.remaining.clone())
                 .or_insert_with(HashMap::new)
                 .insert("STATE".to_string(), new.clone());
 
             let new_score = new.score;
-            pq.push(new, new_score);
+            push_state(&mut pq, &mut beam, new, new_score, ctx.beam_width, stats);
             continue;
         }
 
         // A clumsy workaround because I'm not sure how to set up the iterator types here.
         let no_results = Vec::new();
 
+        // If we've split this chunk too many times, consider only splits that consume the rest of
+        // the chunk. Since `analyze_pada` always allows `Pada::None` at the end of a chunk, at
+        // least one of these splits will produce a word.
+        let at_max_depth = match ctx.max_split_depth {
+            Some(max) => cur.chunk_depth >= max,
+            None => false,
+        };
+
         for split in ctx.sandhi.split_all(&cur.remaining) {
             if !split.is_valid() || split.is_recursive(&cur.remaining) {
                 continue;
             }
+            if at_max_depth && !split.is_end_of_chunk {
+                continue;
+            }
 
             let first = &split.first;
             let second = &split.second;
-            analyze_pada(first, &split, ctx, &mut word_cache)?;
+            if word_cache.contains_key(first) {
+                stats.num_cache_hits += 1;
+            }
+            analyze_pada(first, &split, ctx, &mut word_cache, &mut folded_cache)?;
 
             for semantics in word_cache.get(first).unwrap_or(&no_results) {
                 if !strict_mode::is_valid_word(&cur, &split, semantics) {
@@ -264,15 +1299,34 @@ fn segment(raw_text: &str, ctx: &Segmenter) -> Result<Vec<Word>, Box<dyn Error>>
 
                 let mut new = Phrase {
                     words: cur.words.clone(),
-                    remaining: second.to_string(),
+                    remaining: cur.remaining.clone(),
                     // HACK: this is buggy -- scoring based on cur score set here?
                     score: cur_score,
+                    chunk_depth: if split.is_end_of_chunk {
+                        0
+                    } else {
+                        cur.chunk_depth + 1
+                    },
+                    word_chunks: cur.word_chunks.clone(),
+                    next_boundary: cur.next_boundary,
                 };
-                new.words.push(Word {
-                    text: first.clone(),
-                    semantics: semantics.clone(),
-                });
-                new.score = ctx.model.score(&new);
+                let word = match folded_cache.get(first) {
+                    Some(lower) if *semantics != Pada::None => Word {
+                        text: lower.clone(),
+                        semantics: semantics.clone(),
+                        is_case_folded: true,
+                        boundary: None,
+                    },
+                    _ => Word {
+                        text: first.clone(),
+                        semantics: semantics.clone(),
+                        is_case_folded: false,
+                        boundary: None,
+                    },
+                };
+                new.push_word(word, num_chunks, Boundary::from_split(&split));
+                new.remaining = second.to_string();
+                new.score = ctx.score(&new);
 
                 // Use state "STATE" for now since we don't have any states implemented.
                 let maybe_rival = viterbi_cache
@@ -289,16 +1343,904 @@ fn segment(raw_text: &str, ctx: &Segmenter) -> Result<Vec<Word>, Box<dyn Error>>
                     .entry(new.remaining.clone())
                     .or_insert_with(HashMap::new)
                     .insert("STATE".to_string(), new.clone());
-                pq.push(new, new_score);
+                push_state(&mut pq, &mut beam, new, new_score, ctx.beam_width, stats);
             }
         }
     }
+    stats.num_word_cache_entries = word_cache.len();
+    stats.num_viterbi_entries = viterbi_cache.values().map(|v| v.len()).sum();
 
     // Return the best result we could find above.
     if let Some(solutions) = viterbi_cache.get("") {
         if let Some(best) = solutions.values().max_by_key(|s| s.score) {
-            return Ok(best.words.clone());
+            return Ok(Segmentation {
+                text,
+                words: best.words.clone(),
+                word_chunks: best.word_chunks.clone(),
+            });
+        }
+    }
+    Ok(Segmentation {
+        text,
+        words: Vec::new(),
+        word_chunks: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::path::Path;
+    use std::rc::Rc;
+    use tempfile::{tempdir, TempDir};
+    use vidyut_kosha::semantics::*;
+    use vidyut_kosha::Builder;
+
+    type TestResult = Result<(), Box<dyn Error>>;
+
+    fn avyaya(text: &str) -> Pada {
+        Pada::Avyaya(Avyaya {
+            pratipadika: Pratipadika::Basic {
+                text: text.to_string(),
+                lingas: Vec::new(),
+            },
+        })
+    }
+
+    fn subanta(stem: &str, linga: Linga, vibhakti: Vibhakti) -> Pada {
+        Pada::Subanta(Subanta {
+            pratipadika: Pratipadika::Basic {
+                text: stem.to_string(),
+                lingas: vec![linga],
+            },
+            linga,
+            vacana: Vacana::Eka,
+            vibhakti,
+            is_purvapada: false,
+        })
+    }
+
+    fn tinanta(dhatu: &str) -> Pada {
+        Pada::Tinanta(Tinanta {
+            dhatu: Dhatu(dhatu.to_string()),
+            purusha: Purusha::Prathama,
+            vacana: Vacana::Eka,
+            lakara: Lakara::Lat,
+            pada: PadaPrayoga::Parasmaipada,
+        })
+    }
+
+    /// Writes a tiny set of sandhi rules, lexicon, and model to `dir`.
+    fn write_test_data(dir: &Path) -> TestResult {
+        let config = Config::new(dir);
+        config.create_dirs()?;
+
+        std::fs::write(
+            config.sandhi(),
+            "first,second,result,type\n\
+             a,i,e,\n\
+             as,c,aS c,\n\
+             as,g,o g,\n\
+             s,,H,\n",
+        )?;
+
+        // Keys must be inserted in lexicographic order.
+        let mut words = vec![
+            ("ca", avyaya("ca")),
+            ("devas", subanta("deva", Linga::Pum, Vibhakti::V1)),
+            ("gacCati", tinanta("gam")),
+            ("ILe", tinanta("IL")),
+            ("iti", avyaya("iti")),
+            ("rAmas", subanta("rAma", Linga::Pum, Vibhakti::V1)),
+            ("te", subanta("tad", Linga::Pum, Vibhakti::V1)),
+            ("te", subanta("yuzmad", Linga::None, Vibhakti::V4)),
+        ];
+        words.sort_by(|x, y| x.0.cmp(y.0));
+        let mut builder = Builder::new(config.lexicon())?;
+        for (key, pada) in &words {
+            builder.insert(key, pada)?;
+        }
+        builder.into_lexicon()?;
+
+        std::fs::write(
+            config.model_lemma_counts(),
+            "lemma,tag,count\n\
+             ca,a,10\n\
+             deva,s,5\n\
+             gam,t,5\n\
+             IL,t,5\n\
+             iti,a,10\n\
+             rAma,s,5\n",
+        )?;
+        std::fs::write(
+            config.model_transitions(),
+            "prev_state,cur_state,probability\n",
+        )?;
+        Ok(())
+    }
+
+    /// Creates a segmenter over a tiny set of test data.
+    ///
+    /// The returned `TempDir` must outlive the segmenter's use of its files.
+    fn test_segmenter() -> Result<(Segmenter, TempDir), Box<dyn Error>> {
+        test_segmenter_with(|c| c)
+    }
+
+    /// Creates a segmenter over a tiny set of test data with a custom config.
+    fn test_segmenter_with(
+        f: impl FnOnce(Config) -> Config,
+    ) -> Result<(Segmenter, TempDir), Box<dyn Error>> {
+        let dir = tempdir()?;
+        write_test_data(dir.path())?;
+        let segmenter = Segmenter::new(f(Config::new(dir.path())))?;
+        Ok((segmenter, dir))
+    }
+
+    /// Creates a word with no boundary that was not case-folded.
+    fn word(text: &str, semantics: Pada) -> Word {
+        Word {
+            text: text.to_string(),
+            semantics,
+            is_case_folded: false,
+            boundary: None,
+        }
+    }
+
+    fn texts(words: &[Word]) -> Vec<&str> {
+        words.iter().map(|w| w.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_segment() -> TestResult {
+        let (segmenter, _dir) = test_segmenter()?;
+        let words = segmenter.segment("rAmaS ca gacCati");
+        assert_eq!(texts(&words), vec!["rAmas", "ca", "gacCati"]);
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize_words() -> TestResult {
+        let (segmenter, _dir) = test_segmenter()?;
+        let words = segmenter.segment("rAmaS ca");
+        assert_eq!(
+            serde_json::to_value(&words)?,
+            serde_json::json!([
+                {
+                    "text": "rAmas",
+                    "lemma": "rAma",
+                    "pos": "s",
+                    "is_case_folded": false,
+                    "boundary": null,
+                },
+                {
+                    "text": "ca",
+                    "lemma": "ca",
+                    "pos": "a",
+                    "is_case_folded": false,
+                    "boundary": "sandhi",
+                },
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_segment_with_retroflex_lateral() -> TestResult {
+        let (segmenter, _dir) = test_segmenter()?;
+        let words = segmenter.segment("devaS ca ILe");
+        assert_eq!(texts(&words), vec!["devas", "ca", "ILe"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_lemmas() -> TestResult {
+        let (segmenter, _dir) = test_segmenter()?;
+        let text = "rAmaS ca devaS ca gacCati";
+        let expected: Vec<String> = segmenter
+            .segment(text)
+            .iter()
+            .filter(|w| w.semantics != Pada::None)
+            .map(|w| w.lemma())
+            .collect();
+        assert_eq!(segmenter.lemmas(text), expected);
+        assert_eq!(
+            segmenter.lemmas(text),
+            vec!["rAma", "ca", "deva", "ca", "gam"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_segment_ambiguous() -> TestResult {
+        let (segmenter, _dir) = test_segmenter()?;
+        let words = segmenter.segment_ambiguous("te ca");
+        assert_eq!(
+            words,
+            vec![
+                (
+                    "te".to_string(),
+                    vec![
+                        subanta("tad", Linga::Pum, Vibhakti::V1),
+                        subanta("yuzmad", Linga::None, Vibhakti::V4),
+                    ]
+                ),
+                ("ca".to_string(), vec![avyaya("ca")]),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_segmentations() {
+        let ca = word("ca", avyaya("ca"));
+
+        // `devAs ca` vs. `deva as ca`
+        let a = vec![word("devAs", Pada::None), ca.clone()];
+        let b = vec![
+            word("deva", subanta("deva", Linga::Pum, Vibhakti::Sambodhana)),
+            word("as", tinanta("as")),
+            ca.clone(),
+        ];
+        assert_eq!(
+            diff_segmentations(&a, &b),
+            vec![
+                SegDiff::Replaced {
+                    old: a[..1].to_vec(),
+                    new: b[..2].to_vec()
+                },
+                SegDiff::Same(ca.clone()),
+            ]
+        );
+
+        // Merge, in the other direction.
+        assert_eq!(
+            diff_segmentations(&b, &a),
+            vec![
+                SegDiff::Replaced {
+                    old: b[..2].to_vec(),
+                    new: a[..1].to_vec()
+                },
+                SegDiff::Same(ca.clone()),
+            ]
+        );
+
+        // Changed analysis, insertion, and deletion.
+        let rama = word("rAmas", subanta("rAma", Linga::Pum, Vibhakti::V1));
+        let rama_none = word("rAmas", Pada::None);
+        let a = vec![rama.clone()];
+        let b = vec![rama_none.clone(), ca.clone()];
+        assert_eq!(
+            diff_segmentations(&a, &b),
+            vec![
+                SegDiff::Changed {
+                    old: rama.clone(),
+                    new: rama_none
+                },
+                SegDiff::Inserted(vec![ca.clone()]),
+            ]
+        );
+        let a = vec![ca.clone(), rama.clone()];
+        let b = vec![rama.clone()];
+        assert_eq!(
+            diff_segmentations(&a, &b),
+            vec![SegDiff::Deleted(vec![ca]), SegDiff::Same(rama)]
+        );
+    }
+
+    #[test]
+    fn test_join() -> TestResult {
+        let (segmenter, _dir) = test_segmenter()?;
+        for text in ["rAmaS ca gacCati", "devo gacCati", "rAmaS ca devaH"] {
+            let words = segmenter.segment(text);
+            assert_eq!(segmenter.join(&words), text);
         }
+        assert_eq!(segmenter.join(&[]), "");
+        Ok(())
+    }
+
+    #[test]
+    fn test_join_prefers_spaces() -> TestResult {
+        let (mut segmenter, _dir) = test_segmenter()?;
+        // `vanayatra` sorts before `vane 'tra`, but we should still prefer the space.
+        segmenter.sandhi = Sandhi::from_reader(
+            "first,second,result,type\n\
+             e,a,aya,\n\
+             e,a,e ',\n"
+                .as_bytes(),
+        )?;
+        let words = [word("vane", Pada::None), word("atra", Pada::None)];
+        assert_eq!(segmenter.join(&words), "vane 'tra");
+        Ok(())
+    }
+
+    #[test]
+    fn test_segment_with_alternatives() -> TestResult {
+        let (segmenter, _dir) = test_segmenter()?;
+        let words = segmenter.segment_with_alternatives("te ca");
+        assert_eq!(words.len(), 2);
+
+        let te = &words[0];
+        let tad = subanta("tad", Linga::Pum, Vibhakti::V1);
+        let yuzmad = subanta("yuzmad", Linga::None, Vibhakti::V4);
+        assert_eq!(te.word.text, "te");
+        assert_eq!(te.alternatives.len(), 1);
+        let (alternative, score) = &te.alternatives[0];
+        if te.word.semantics == tad {
+            assert_eq!(*alternative, yuzmad);
+        } else {
+            assert_eq!(*alternative, tad);
+        }
+        assert!(*score <= te.score);
+
+        // `ca` is unambiguous.
+        assert!(words[1].alternatives.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_segment_prefix() -> TestResult {
+        let (segmenter, _dir) = test_segmenter()?;
+        let text = "rAmaS ca devo gacCati rAmaS ca devaS ca gacCati";
+        let words = segmenter.segment(text);
+        for n in 0..=words.len() + 1 {
+            let expected = &words[..cmp::min(n, words.len())];
+            assert_eq!(segmenter.segment_prefix(text, n), expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_segment_with_extra_lexicon() -> TestResult {
+        let bilva = subanta("bilva", Linga::Pum, Vibhakti::V1);
+        let mut terms = HashMap::new();
+        terms.insert("bilvas".to_string(), vec![bilva.clone()]);
+        // Analyses for words in the main lexicon are merged.
+        terms.insert("ca".to_string(), vec![avyaya("ca"), avyaya("cid")]);
+
+        let (segmenter, _dir) = test_segmenter()?;
+        let words = segmenter.segment("bilvaS ca");
+        assert_eq!(words[0].semantics, Pada::None);
+
+        let segmenter = segmenter.with_lexicon(terms);
+        let words = segmenter.segment("bilvaS ca");
+        assert_eq!(texts(&words), vec!["bilvas", "ca"]);
+        assert_eq!(words[0].semantics, bilva);
+        assert_eq!(
+            segmenter.segment_ambiguous("ca")[0].1,
+            vec![avyaya("ca"), avyaya("cid")]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_segment_raw() -> TestResult {
+        let (segmenter, _dir) = test_segmenter()?;
+        // For normalized text, `segment_raw` and `segment` agree.
+        for text in ["rAmaS ca gacCati", "devo gacCati . rAmaS ca"] {
+            assert_eq!(segmenter.segment_raw(text), segmenter.segment(text));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_segment_verbose() -> TestResult {
+        let (segmenter, _dir) = test_segmenter()?;
+        let (text, words) = segmenter.segment_verbose("  rAmaS   ca\n gacCati ");
+        assert_eq!(text, "rAmaS ca gacCati");
+        assert_eq!(words, segmenter.segment("rAmaS ca gacCati"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_segmentation() -> TestResult {
+        let (segmenter, _dir) = test_segmenter()?;
+        let words = segmenter.segment("rAmaS ca gacCati");
+        assert!(check_segmentation(&words).is_empty());
+
+        let words = segmenter.segment("rAmaS ca ca gacCati");
+        assert_eq!(texts(&words), vec!["rAmas", "ca", "ca", "gacCati"]);
+        assert_eq!(
+            check_segmentation(&words),
+            vec![SegWarning::RepeatedWord { index: 1 }]
+        );
+
+        // Words with the same text but different semantics are not repeats.
+        let te = segmenter.segment_ambiguous("te")[0].1.clone();
+        let words = [word("te", te[0].clone()), word("te", te[1].clone())];
+        assert!(check_segmentation(&words).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_agreement() -> TestResult {
+        let mut terms = HashMap::new();
+        terms.insert(
+            "bilvas".to_string(),
+            vec![subanta("bilva", Linga::Pum, Vibhakti::V1)],
+        );
+        let (a, _dir_a) = test_segmenter()?;
+        let (b, _dir_b) = test_segmenter()?;
+        let b = b.with_lexicon(terms);
+
+        let texts = ["rAmaS ca gacCati", "bilvaS ca"];
+        let report = agreement(&a, &b, &texts);
+        assert_eq!(report.num_words, 5);
+        // Both segmenters find `bilvas`, but only `b` can analyze it.
+        assert_eq!(report.num_shared_words, 5);
+        assert_eq!(report.num_shared_analyses, 4);
+        assert_eq!(report.word_agreement(), 1.0);
+        assert_eq!(report.analysis_agreement(), 0.8);
+        assert_eq!(
+            report.most_divergent,
+            vec![TextAgreement {
+                text: "bilvaS ca".to_string(),
+                word_agreement: 1.0,
+                analysis_agreement: 0.5,
+            }]
+        );
+
+        // A segmenter fully agrees with itself.
+        let report = agreement(&a, &a, &texts);
+        assert_eq!(report.analysis_agreement(), 1.0);
+        assert!(report.most_divergent.is_empty());
+        assert_eq!(agreement(&a, &b, &[]).analysis_agreement(), 1.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_segment_with_beam_width() -> TestResult {
+        let (exact, _dir) = test_segmenter()?;
+        let (beam, _dir) = test_segmenter_with(|c| c.with_beam_width(1))?;
+
+        let text = "rAmaS ca gacCati";
+        assert_eq!(beam.segment(text), exact.segment(text));
+
+        let text = "devo rAmo gacCati ca ".repeat(10);
+        let exact_stats = exact.segment_stats(&text);
+        let beam_stats = beam.segment_stats(&text);
+        assert!(beam_stats.max_queue_len < exact_stats.max_queue_len);
+        assert_eq!(texts(&beam.segment(&text)), texts(&exact.segment(&text)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_push_state_prunes_per_suffix() {
+        let state = |remaining: &str, score| Phrase {
+            score,
+            ..Phrase::new(remaining.to_string())
+        };
+        let mut pq = DoublePriorityQueue::new();
+        let mut beam = Beam::new();
+        let mut stats = SearchStats::default();
+
+        // A global cap of 1 would drop `ca`, since it scores lower than `gacCati`. But the two
+        // states have different suffixes, so we keep both.
+        push_state(
+            &mut pq,
+            &mut beam,
+            state("gacCati", -1),
+            -1,
+            Some(1),
+            &mut stats,
+        );
+        push_state(&mut pq, &mut beam, state("ca", -5), -5, Some(1), &mut stats);
+        assert_eq!(pq.len(), 2);
+
+        // A better state for `ca` replaces the worse one.
+        push_state(&mut pq, &mut beam, state("ca", -3), -3, Some(1), &mut stats);
+        assert_eq!(pq.len(), 2);
+        let mut scores: Vec<_> = pq.iter().map(|(_, &score)| score).collect();
+        scores.sort();
+        assert_eq!(scores, vec![-3, -1]);
+
+        // Popping a state frees its slot in the beam.
+        assert_eq!(
+            pop_state(&mut pq, &mut beam).map(|(_, score)| score),
+            Some(-1)
+        );
+        push_state(
+            &mut pq,
+            &mut beam,
+            state("gacCati", -9),
+            -9,
+            Some(1),
+            &mut stats,
+        );
+        assert_eq!(pq.len(), 2);
+    }
+
+    #[test]
+    fn test_gloss() {
+        assert_eq!(
+            word("gacCati", tinanta("gam")).gloss(),
+            "gacCati: √gam, lat, 3s"
+        );
+        assert_eq!(
+            word("rAmas", subanta("rAma", Linga::Pum, Vibhakti::V1)).gloss(),
+            "rAmas: rAma, m, 1s"
+        );
+        assert_eq!(word("ca", avyaya("ca")).gloss(), "ca: ca, avyaya");
+        assert_eq!(word("kaH", Pada::None).gloss(), "kaH: ?");
+    }
+
+    #[test]
+    fn test_display_compounds() {
+        let mut raja = subanta("rAjan", Linga::Pum, Vibhakti::V1);
+        if let Pada::Subanta(s) = &mut raja {
+            s.is_purvapada = true;
+        }
+
+        let words = vec![
+            word("rAja", raja),
+            word("puruzas", subanta("puruza", Linga::Pum, Vibhakti::V1)),
+            word("gacCati", tinanta("gam")),
+        ];
+        assert_eq!(display_compounds(&words, "-"), "rAja-puruzas gacCati");
+        assert_eq!(display_compounds(&words, ""), "rAjapuruzas gacCati");
+        assert_eq!(display_compounds(&words[1..], "-"), "puruzas gacCati");
+        assert_eq!(display_compounds(&[], "-"), "");
+    }
+
+    #[test]
+    fn test_from_data() -> TestResult {
+        let dir = tempdir()?;
+        write_test_data(dir.path())?;
+        let config = Config::new(dir.path());
+        let read = |p: &Path| std::fs::read(p);
+
+        let segmenter = Segmenter::from_data(
+            Sandhi::from_reader(read(config.sandhi())?.as_slice())?,
+            Kosha::from_bytes(
+                read(&config.lexicon().join("padas.fst"))?,
+                &read(&config.lexicon().join("pratipadikas.csv"))?,
+                &read(&config.lexicon().join("dhatus.csv"))?,
+            )?,
+            Model::from_bytes(
+                &read(&config.model_lemma_counts())?,
+                &read(&config.model_transitions())?,
+            )?,
+        );
+        let words = segmenter.segment("rAmaS ca gacCati");
+        assert_eq!(texts(&words), vec!["rAmas", "ca", "gacCati"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_segment_with_lenient_case() -> TestResult {
+        let (strict, _dir) = test_segmenter()?;
+        let words = strict.segment("gacCati ITI rAmas");
+        assert_eq!(texts(&words), vec!["gacCati", "ITI", "rAmas"]);
+        assert_eq!(words[1].semantics, Pada::None);
+        assert!(!words[1].is_case_folded);
+
+        let (lenient, _dir) = test_segmenter_with(|c| c.with_lenient_case(true))?;
+        let words = lenient.segment("gacCati ITI rAmas");
+        assert_eq!(texts(&words), vec!["gacCati", "iti", "rAmas"]);
+        assert_eq!(words[1].lemma(), "iti");
+        assert!(words[1].is_case_folded);
+
+        // Words that match exactly are never folded.
+        assert!(!words[0].is_case_folded);
+        assert!(!words[2].is_case_folded);
+        Ok(())
+    }
+
+    #[test]
+    fn test_segment_with_lenient_case_rejects_ambiguous_fold() -> TestResult {
+        let (lenient, _dir) = test_segmenter_with(|c| c.with_lenient_case(true))?;
+        // `te` has two analyses, so we don't know which one `Te` was meant to be.
+        let words = lenient.segment("rAmas Te");
+        assert_eq!(texts(&words), vec!["rAmas", "Te"]);
+        assert_eq!(words[1].semantics, Pada::None);
+        assert!(!words[1].is_case_folded);
+        Ok(())
+    }
+
+    #[test]
+    fn test_segment_with_punctuation() -> TestResult {
+        let (segmenter, _dir) = test_segmenter()?;
+        let words = segmenter.segment("devaH, gacCati.");
+        assert_eq!(texts(&words), vec!["devas", ",", "gacCati", "."]);
+        assert_eq!(words[1].semantics, Pada::None);
+        assert_eq!(words[3].semantics, Pada::None);
+
+        let words = segmenter.segment("rAmaS ca (gacCati) 12, iti ||");
+        assert_eq!(
+            texts(&words),
+            vec!["rAmas", "ca", "(", "gacCati", ")", "12", ",", "iti", "||"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_segment_with_danda() -> TestResult {
+        let (segmenter, _dir) = test_segmenter()?;
+        let words = segmenter.segment("rAmaH . sItA |");
+        assert_eq!(texts(&words), vec!["rAmas", ".", "sItA", "|"]);
+        let dandas: Vec<_> = words.iter().map(|w| w.is_danda()).collect();
+        assert_eq!(dandas, vec![false, true, false, true]);
+
+        let words = segmenter.segment("devaH, gacCati .. iti ॥ 1 ॥");
+        assert_eq!(
+            texts(&words),
+            vec!["devas", ",", "gacCati", "..", "iti", "॥", "1", "॥"]
+        );
+        let dandas: Vec<_> = words.iter().map(|w| w.is_danda()).collect();
+        assert_eq!(
+            dandas,
+            vec![false, false, false, true, false, true, false, true]
+        );
+
+        // Dandas are never merged with unknown words.
+        let words = merge_unknown_words(segmenter.segment("sItA | 123 ||"));
+        assert_eq!(texts(&words), vec!["sItA", "|", "123", "||"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_segment_boundaries() -> TestResult {
+        let (segmenter, _dir) = test_segmenter()?;
+        let words = segmenter.segment("rAmaS ca gacCati iti");
+        let boundaries: Vec<_> = words.iter().map(|w| w.boundary).collect();
+        assert_eq!(
+            boundaries,
+            vec![
+                None,
+                Some(Boundary::Sandhi),
+                Some(Boundary::Space),
+                Some(Boundary::Space),
+            ]
+        );
+
+        let words = segmenter.segment("devaScagacCati");
+        assert_eq!(texts(&words), vec!["devas", "ca", "gacCati"]);
+        assert_eq!(words[1].boundary, Some(Boundary::Sandhi));
+        assert_eq!(words[2].boundary, Some(Boundary::Join));
+        Ok(())
+    }
+
+    #[test]
+    fn test_warmup() -> TestResult {
+        let (segmenter, _dir) = test_segmenter()?;
+        segmenter.warmup();
+        let words = segmenter.segment("rAmaS ca gacCati");
+        assert_eq!(texts(&words), vec!["rAmas", "ca", "gacCati"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_segment_keeps_pluta() -> TestResult {
+        let (segmenter, _dir) = test_segmenter()?;
+        let words = segmenter.segment("o3m iti");
+        assert_eq!(texts(&words), vec!["o3m", "iti"]);
+
+        // A `3` that doesn't follow a vowel is not a pluta marker.
+        let words = segmenter.segment("iti || 3 ||");
+        assert_eq!(texts(&words), vec!["iti", "||", "3", "||"]);
+        assert_eq!(words[2].semantics, Pada::None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_unknown_words() -> TestResult {
+        let (segmenter, _dir) = test_segmenter()?;
+        let words = merge_unknown_words(segmenter.segment("gacCati kim123 -- devaH"));
+        assert_eq!(texts(&words), vec!["gacCati", "kim 123 --", "devas"]);
+        assert_eq!(words[1].semantics, Pada::None);
+
+        // Known words are never merged.
+        let words = merge_unknown_words(segmenter.segment("rAmaS ca gacCati"));
+        assert_eq!(texts(&words), vec!["rAmas", "ca", "gacCati"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_segment_stats() -> TestResult {
+        let (segmenter, _dir) = test_segmenter()?;
+        let stats = segmenter.segment_stats("rAmaS ca gacCati");
+        assert!(stats.num_pops > 0);
+        assert!(stats.num_pushes >= stats.num_pops);
+        assert!(stats.num_word_cache_entries > 0);
+        assert!(stats.num_viterbi_entries > 0);
+
+        // The search is deterministic, so the same input should produce the same counts.
+        assert_eq!(stats, segmenter.segment_stats("rAmaS ca gacCati"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_segment_with_unknown_word_penalty() -> TestResult {
+        // `te` has a lemma that the model hasn't seen, so the model scores it the same as
+        // skipping it. With a length penalty, the model should always prefer analyzing it.
+        let (segmenter, _dir) = test_segmenter_with(|c| c.with_unknown_word_penalty(1.0))?;
+        let words = segmenter.segment("te");
+        assert_eq!(texts(&words), vec!["te"]);
+        assert_ne!(words[0].semantics, Pada::None);
+
+        let words = segmenter.segment("te ca te");
+        assert!(words.iter().all(|w| w.semantics != Pada::None));
+
+        // Junk is still skipped.
+        let words = segmenter.segment("te 123");
+        assert_eq!(texts(&words), vec!["te", "123"]);
+        assert_eq!(words[1].semantics, Pada::None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_score_breakdown() -> TestResult {
+        let (segmenter, _dir) = test_segmenter_with(|c| c.with_unknown_word_penalty(1.0))?;
+        let words = segmenter.segment("devaH rAmo gacCati 123");
+        assert_eq!(texts(&words), vec!["devas", "rAmas", "gacCati", "123"]);
+
+        let breakdown = segmenter.score_breakdown(&words);
+        assert_eq!(breakdown.len(), words.len());
+        assert!(breakdown[..3].iter().all(|c| c.penalty == 0));
+        assert_eq!(breakdown[3].penalty, 300);
+
+        // The totals should add up to the score of the full phrase.
+        let mut phrase = Phrase::new(String::new());
+        for word in &words {
+            phrase.words.push(word.clone());
+            phrase.score = segmenter.score(&phrase);
+        }
+        let total: i32 = breakdown.iter().map(|c| c.total()).sum();
+        assert_eq!(total, phrase.score);
+        Ok(())
+    }
+
+    #[test]
+    fn test_segment_with_max_split_depth() -> TestResult {
+        let text = "ca".repeat(20);
+
+        let (unlimited, _dir) = test_segmenter()?;
+        let (limited, _dir) = test_segmenter_with(|c| c.with_max_split_depth(2))?;
+
+        // The limited segmenter never splits a chunk more than twice.
+        let words = limited.segment(&text);
+        assert!(words.len() <= 3);
+        assert_eq!(texts(&words).concat(), text);
+
+        let unlimited_stats = unlimited.segment_stats(&text);
+        let limited_stats = limited.segment_stats(&text);
+        assert!(limited_stats.num_pushes < unlimited_stats.num_pushes);
+        Ok(())
+    }
+
+    /// A lexicon that records the length of the longest key it was asked to look up.
+    struct LongestLookup(Rc<Cell<usize>>);
+
+    impl Lexicon for LongestLookup {
+        fn lookup(&self, key: &str) -> Result<Vec<Pada>, Box<dyn Error>> {
+            self.0.set(self.0.get().max(key.len()));
+            Ok(Vec::new())
+        }
+    }
+
+    #[test]
+    fn test_segment_with_max_word_length() -> TestResult {
+        let junk = "ka".repeat(100);
+        let max_len = segmenter_lexicon_max_len()?;
+
+        let longest = Rc::new(Cell::new(0));
+        let (segmenter, _dir) = test_segmenter()?;
+        let segmenter = segmenter.with_lexicon(LongestLookup(longest.clone()));
+        segmenter.segment(&junk);
+        assert!(longest.get() > max_len);
+
+        let longest = Rc::new(Cell::new(0));
+        let (segmenter, _dir) = test_segmenter_with(|c| c.with_max_word_length(max_len))?;
+        let segmenter = segmenter.with_lexicon(LongestLookup(longest.clone()));
+        let words = segmenter.segment(&junk);
+        assert!(longest.get() <= max_len);
+        assert_eq!(texts(&words).concat(), junk);
+
+        // Real words are still found.
+        let words = segmenter.segment("rAmaS ca gacCati");
+        assert_eq!(texts(&words), vec!["rAmas", "ca", "gacCati"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_segment_with_max_word_length_boundary() -> TestResult {
+        let len = "gacCati".len();
+
+        // A word exactly at the limit is looked up.
+        let (segmenter, _dir) = test_segmenter_with(|c| c.with_max_word_length(len))?;
+        let words = segmenter.segment("gacCati");
+        assert_eq!(texts(&words), vec!["gacCati"]);
+        assert_eq!(words[0].semantics, tinanta("gam"));
+
+        // A word one byte over the limit is skipped.
+        let (segmenter, _dir) = test_segmenter_with(|c| c.with_max_word_length(len - 1))?;
+        let words = segmenter.segment("gacCati");
+        assert_eq!(texts(&words), vec!["gacCati"]);
+        assert_eq!(words[0].semantics, Pada::None);
+        Ok(())
+    }
+
+    /// Returns the length of the longest word in the test lexicon.
+    fn segmenter_lexicon_max_len() -> Result<usize, Box<dyn Error>> {
+        let (segmenter, _dir) = test_segmenter()?;
+        Ok(segmenter.lexicon().max_key_len())
+    }
+
+    #[test]
+    fn test_resegment() -> TestResult {
+        let (segmenter, _dir) = test_segmenter()?;
+        let prev = segmenter.segment_incremental("devas iti rAmaS ca gacCati iti");
+        assert_eq!(
+            texts(prev.words()),
+            vec!["devas", "iti", "rAmas", "ca", "gacCati", "iti"]
+        );
+
+        // Replace "devas" with "rAmas".
+        let next = segmenter.resegment(&prev, 0..5, "rAmas");
+        assert_eq!(next.text(), "rAmas iti rAmaS ca gacCati iti");
+        assert_eq!(next, segmenter.segment_incremental(next.text()));
+
+        // Replace "ca" with "ca devas".
+        let start = prev.text().find(" ca ").unwrap() + 1;
+        let next = segmenter.resegment(&prev, start..start + 2, "ca devas");
+        assert_eq!(
+            texts(next.words()),
+            vec!["devas", "iti", "rAmas", "ca", "devas", "gacCati", "iti"]
+        );
+        assert_eq!(next, segmenter.segment_incremental(next.text()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_resegment_at_end_of_text() -> TestResult {
+        let (segmenter, _dir) = test_segmenter()?;
+        let prev = segmenter.segment_incremental("rAmaS ca gacCati");
+        let end = prev.text().len();
+
+        // Append a word.
+        let next = segmenter.resegment(&prev, end..end, " iti");
+        assert_eq!(next.text(), "rAmaS ca gacCati iti");
+        assert_eq!(texts(next.words()), vec!["rAmas", "ca", "gacCati", "iti"]);
+        assert_eq!(next, segmenter.segment_incremental(next.text()));
+
+        // Replace the last word.
+        let next = segmenter.resegment(&prev, end - "gacCati".len()..end, "iti");
+        assert_eq!(next, segmenter.segment_incremental("rAmaS ca iti"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_resegment_with_invalid_edit() -> TestResult {
+        let (segmenter, _dir) = test_segmenter()?;
+        let prev = segmenter.segment_incremental("rAmaS ca gacCati");
+        let end = prev.text().len();
+
+        assert_eq!(segmenter.resegment(&prev, end..end + 1, "iti"), prev);
+        let reversed = Range { start: 3, end: 1 };
+        assert_eq!(segmenter.resegment(&prev, reversed, "iti"), prev);
+
+        // "Ā" is two bytes long, so offset 1 is not a char boundary.
+        let prev = segmenter.segment_incremental("Ā ca");
+        assert_eq!(segmenter.resegment(&prev, 1..2, "iti"), prev);
+        Ok(())
+    }
+
+    #[test]
+    fn test_resegment_across_sandhi_boundary() -> TestResult {
+        let (segmenter, _dir) = test_segmenter()?;
+        let prev = segmenter.segment_incremental("iti iti rAmaS ca iti iti");
+
+        // Replace the "ca" that conditions the sandhi in "rAmaS". The window must grow to include
+        // "rAmaS", which changes from "rAmaS" to "rAmo".
+        let start = prev.text().find("rAmaS").unwrap();
+        let next = segmenter.resegment(&prev, start..start + 8, "rAmo gacCati");
+        assert_eq!(next.text(), "iti iti rAmo gacCati iti iti");
+        assert_eq!(
+            texts(next.words()),
+            vec!["iti", "iti", "rAmas", "gacCati", "iti", "iti"]
+        );
+        assert_eq!(next, segmenter.segment_incremental(next.text()));
+        Ok(())
     }
-    Ok(Vec::new())
 }
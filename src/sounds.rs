@@ -2,6 +2,7 @@
 
 use lazy_static::lazy_static;
 use regex::Regex;
+use std::ops::Range;
 
 fn match_char(c: &char, re: &Regex) -> bool {
     // Avoid `to_string`, which will create a new string on the heap.
@@ -19,11 +20,25 @@ fn match_char(c: &char, re: &Regex) -> bool {
 pub fn is_sanskrit(c: char) -> bool {
     lazy_static! {
         static ref RE: Regex =
-            Regex::new(r"[aAiIuUfFxXeEoOMHkKgGNcCjJYwWqQRtTdDnpPbBmyrlvSzshL']").unwrap();
+            Regex::new(r"[aAiIuUfFxXeEoOMHZVkKgGNcCjJYwWqQRtTdDnpPbBmyrlvSzshL']").unwrap();
     }
     match_char(&c, &RE)
 }
 
+/// Returns whether the character that starts at byte offset `i` of `text` is Sanskrit.
+///
+/// This is the same as `is_sanskrit`, except that we also accept a `3` that directly follows a
+/// vowel. SLP1 uses this `3` to mark a pluta (prolonged) vowel, as in `o3m`, so it should stay
+/// attached to its vowel during segmentation. Any other `3`, such as the one in `|| 3 ||`, is not
+/// Sanskrit. If `i` is out of bounds or not at a character boundary, returns false.
+pub fn is_sanskrit_at(text: &str, i: usize) -> bool {
+    match text.get(i..).and_then(|s| s.chars().next()) {
+        Some('3') => text[..i].chars().next_back().is_some_and(is_ac),
+        Some(c) => is_sanskrit(c),
+        None => false,
+    }
+}
+
 pub fn is_ac(c: char) -> bool {
     lazy_static! {
         // Matches all non-sounds at the beginning of the string.
@@ -35,18 +50,436 @@ pub fn is_ac(c: char) -> bool {
 pub fn is_ghosha(c: char) -> bool {
     lazy_static! {
         // Matches all voiced sounds the beginning of the string.
-        static ref RE: Regex = Regex::new(r"[aAiIuUfFxXeEoOgGNjJYqQRdDnbBmyrlvh]").unwrap();
+        static ref RE: Regex = Regex::new(r"[aAiIuUfFxXeEoOgGNjJYqQRdDnbBmyrlvhL]").unwrap();
     }
     match_char(&c, &RE)
 }
 
+/// Returns the number of morae (*mātrā*s) in each syllable of the given SLP1 text.
+///
+/// A syllable has one mora if it is light (*laghu*) and two if it is heavy (*guru*). A syllable is
+/// heavy if its vowel is long, if its vowel is followed by an anusvara or visarga, or if its
+/// vowel is followed by two or more consonants. Word boundaries don't affect weight, so a
+/// consonant cluster that spans two words still makes the syllable before it heavy. At the end
+/// of the text, a single consonant is enough to close the final syllable and make it heavy.
+///
+/// A pluta vowel (e.g. `o3`) has three morae. All non-Sanskrit characters are ignored.
+fn syllable_morae(text: &str) -> Vec<usize> {
+    let chars: Vec<char> = text
+        .char_indices()
+        .filter(|(i, c)| is_sanskrit_at(text, *i) && *c != '\'')
+        .map(|(_, c)| c)
+        .collect();
+
+    let mut ret = Vec::new();
+    for (i, c) in chars.iter().enumerate() {
+        if !is_ac(*c) {
+            continue;
+        }
+        let rest = &chars[i + 1..];
+        let morae = if rest.first() == Some(&'3') {
+            3
+        } else if "AIUFXeEoO".contains(*c) {
+            2
+        } else {
+            let num_following = rest
+                .iter()
+                .take_while(|c| !is_ac(**c))
+                .filter(|c| **c != '3')
+                .count();
+            let is_last = num_following == rest.len();
+            let has_ayogavaha = matches!(rest.first(), Some('M' | 'H' | 'Z' | 'V'));
+            if has_ayogavaha || num_following >= 2 || (is_last && num_following >= 1) {
+                2
+            } else {
+                1
+            }
+        };
+        ret.push(morae);
+    }
+    ret
+}
+
+/// Returns the number of syllables in the given SLP1 text.
+///
+/// Each vowel starts a new syllable. All non-Sanskrit characters are ignored.
+pub fn syllable_count(text: &str) -> usize {
+    syllable_morae(text).len()
+}
+
+/// Returns the number of morae (*mātrā*s) in the given SLP1 text.
+///
+/// A light (*laghu*) syllable has one mora and a heavy (*guru*) syllable has two. A syllable is
+/// heavy if it has a long vowel, an anusvara or visarga, or a following consonant cluster, and
+/// the final syllable of the text is also heavy if it ends in a consonant. Since word boundaries
+/// don't affect weight, pass a full verse or *pāda* rather than counting each word separately.
+pub fn mora_count(text: &str) -> usize {
+    syllable_morae(text).iter().sum()
+}
+
+/// A run of text that is either all Sanskrit or all non-Sanskrit. For details, see `chunk`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Chunk {
+    /// The byte span of this chunk in the original text.
+    pub span: Range<usize>,
+    /// Whether every character in this chunk passes `is_sanskrit_at`.
+    pub is_sanskrit: bool,
+}
+
+/// Splits SLP1 text into chunks of Sanskrit and non-Sanskrit characters.
+///
+/// Chunks are separated by whitespace and by changes between Sanskrit and non-Sanskrit
+/// characters, as defined by `is_sanskrit_at`. Whitespace itself is not part of any chunk. For
+/// example, `rAmaH 12 gacCati.` has the chunks `rAmaH`, `12`, `gacCati`, and `.`.
+pub fn chunk(text: &str) -> Vec<Chunk> {
+    let mut ret: Vec<Chunk> = Vec::new();
+    let mut prev_end = None;
+    for (i, c) in text.char_indices() {
+        if c.is_whitespace() {
+            prev_end = None;
+            continue;
+        }
+        let end = i + c.len_utf8();
+        let is_sanskrit = is_sanskrit_at(text, i);
+        match ret.last_mut() {
+            Some(last) if prev_end == Some(i) && last.is_sanskrit == is_sanskrit => {
+                last.span.end = end;
+            }
+            _ => ret.push(Chunk {
+                span: i..end,
+                is_sanskrit,
+            }),
+        }
+        prev_end = Some(end);
+    }
+    ret
+}
+
+/// The place of articulation (*sthāna*) of a Sanskrit sound.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Place {
+    /// The throat, as in `a`, `k`, and `h`.
+    Kantha,
+    /// The palate, as in `i`, `c`, and `S`.
+    Talu,
+    /// The roof of the mouth, as in `f`, `w`, and `z`.
+    Murdha,
+    /// The teeth, as in `x`, `t`, and `s`.
+    Danta,
+    /// The lips, as in `u` and `p`.
+    Oshtha,
+    /// The throat and palate, as in `e` and `E`.
+    KanthaTalu,
+    /// The throat and lips, as in `o` and `O`.
+    KanthaOshtha,
+    /// The teeth and lips, as in `v`.
+    DantaOshtha,
+    /// The nose, as in the anusvara `M`.
+    Nasika,
+    /// The root of the tongue, as in the jihvamuliya `Z`.
+    Jihvamula,
+}
+
+/// The manner of articulation of a Sanskrit sound, i.e. its broad phonetic class.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Manner {
+    /// A vowel (*svara*), as in `a`, `f`, and `O`.
+    Svara,
+    /// A stop (*sparśa*) other than a nasal, as in `k`, `J`, and `b`.
+    Sparsha,
+    /// A nasal stop (*anunāsika*), as in `N`, `R`, and `m`.
+    Anunasika,
+    /// A semivowel (*antaḥstha*), as in `y`, `r`, `l`, `v`, and `L`.
+    Antahstha,
+    /// A sibilant or aspirate (*ūṣman*), as in `S`, `z`, `s`, and `h`.
+    Ushman,
+    /// A sound that depends on a neighboring sound (*ayogavāha*), such as the anusvara `M` and
+    /// the visarga `H`.
+    Ayogavaha,
+}
+
+/// A Sanskrit sound, as represented by a single SLP1 character.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Sound {
+    /// SLP1 `a`
+    A,
+    /// SLP1 `A`
+    Aa,
+    /// SLP1 `i`
+    I,
+    /// SLP1 `I`
+    Ii,
+    /// SLP1 `u`
+    U,
+    /// SLP1 `U`
+    Uu,
+    /// SLP1 `f`
+    Ri,
+    /// SLP1 `F`
+    Rii,
+    /// SLP1 `x`
+    Li,
+    /// SLP1 `X`
+    Lii,
+    /// SLP1 `e`
+    E,
+    /// SLP1 `E`
+    Ai,
+    /// SLP1 `o`
+    O,
+    /// SLP1 `O`
+    Au,
+    /// SLP1 `M`
+    Anusvara,
+    /// SLP1 `H`
+    Visarga,
+    /// SLP1 `Z`
+    Jihvamuliya,
+    /// SLP1 `V`
+    Upadhmaniya,
+    /// SLP1 `k`
+    Ka,
+    /// SLP1 `K`
+    Kha,
+    /// SLP1 `g`
+    Ga,
+    /// SLP1 `G`
+    Gha,
+    /// SLP1 `N`
+    Nga,
+    /// SLP1 `c`
+    Ca,
+    /// SLP1 `C`
+    Cha,
+    /// SLP1 `j`
+    Ja,
+    /// SLP1 `J`
+    Jha,
+    /// SLP1 `Y`
+    Nya,
+    /// SLP1 `w`
+    Tta,
+    /// SLP1 `W`
+    Ttha,
+    /// SLP1 `q`
+    Dda,
+    /// SLP1 `Q`
+    Ddha,
+    /// SLP1 `R`
+    Nna,
+    /// SLP1 `t`
+    Ta,
+    /// SLP1 `T`
+    Tha,
+    /// SLP1 `d`
+    Da,
+    /// SLP1 `D`
+    Dha,
+    /// SLP1 `n`
+    Na,
+    /// SLP1 `p`
+    Pa,
+    /// SLP1 `P`
+    Pha,
+    /// SLP1 `b`
+    Ba,
+    /// SLP1 `B`
+    Bha,
+    /// SLP1 `m`
+    Ma,
+    /// SLP1 `y`
+    Ya,
+    /// SLP1 `r`
+    Ra,
+    /// SLP1 `l`
+    La,
+    /// SLP1 `v`
+    Va,
+    /// SLP1 `S`
+    Sha,
+    /// SLP1 `z`
+    Ssa,
+    /// SLP1 `s`
+    Sa,
+    /// SLP1 `h`
+    Ha,
+    /// SLP1 `L`
+    Lla,
+}
+
+impl Sound {
+    /// Returns the SLP1 character for this sound.
+    pub fn to_char(&self) -> char {
+        use Sound::*;
+        match self {
+            A => 'a',
+            Aa => 'A',
+            I => 'i',
+            Ii => 'I',
+            U => 'u',
+            Uu => 'U',
+            Ri => 'f',
+            Rii => 'F',
+            Li => 'x',
+            Lii => 'X',
+            E => 'e',
+            Ai => 'E',
+            O => 'o',
+            Au => 'O',
+            Anusvara => 'M',
+            Visarga => 'H',
+            Jihvamuliya => 'Z',
+            Upadhmaniya => 'V',
+            Ka => 'k',
+            Kha => 'K',
+            Ga => 'g',
+            Gha => 'G',
+            Nga => 'N',
+            Ca => 'c',
+            Cha => 'C',
+            Ja => 'j',
+            Jha => 'J',
+            Nya => 'Y',
+            Tta => 'w',
+            Ttha => 'W',
+            Dda => 'q',
+            Ddha => 'Q',
+            Nna => 'R',
+            Ta => 't',
+            Tha => 'T',
+            Da => 'd',
+            Dha => 'D',
+            Na => 'n',
+            Pa => 'p',
+            Pha => 'P',
+            Ba => 'b',
+            Bha => 'B',
+            Ma => 'm',
+            Ya => 'y',
+            Ra => 'r',
+            La => 'l',
+            Va => 'v',
+            Sha => 'S',
+            Ssa => 'z',
+            Sa => 's',
+            Ha => 'h',
+            Lla => 'L',
+        }
+    }
+
+    /// Returns whether this sound is a vowel (*ac*).
+    pub fn is_vowel(&self) -> bool {
+        is_ac(self.to_char())
+    }
+
+    /// Returns whether this sound is voiced (*ghoṣa*).
+    pub fn is_voiced(&self) -> bool {
+        is_ghosha(self.to_char())
+    }
+
+    /// Returns this sound's place of articulation.
+    pub fn place(&self) -> Place {
+        use Sound::*;
+        match self {
+            A | Aa | Visarga | Ka | Kha | Ga | Gha | Nga | Ha => Place::Kantha,
+            I | Ii | Ca | Cha | Ja | Jha | Nya | Ya | Sha => Place::Talu,
+            U | Uu | Upadhmaniya | Pa | Pha | Ba | Bha | Ma => Place::Oshtha,
+            Ri | Rii | Tta | Ttha | Dda | Ddha | Nna | Ra | Ssa | Lla => Place::Murdha,
+            Li | Lii | Ta | Tha | Da | Dha | Na | La | Sa => Place::Danta,
+            E | Ai => Place::KanthaTalu,
+            O | Au => Place::KanthaOshtha,
+            Anusvara => Place::Nasika,
+            Jihvamuliya => Place::Jihvamula,
+            Va => Place::DantaOshtha,
+        }
+    }
+
+    /// Returns this sound's manner of articulation.
+    pub fn manner(&self) -> Manner {
+        use Sound::*;
+        match self {
+            A | Aa | I | Ii | U | Uu | Ri | Rii | Li | Lii | E | Ai | O | Au => Manner::Svara,
+            Ka | Kha | Ga | Gha | Ca | Cha | Ja | Jha | Tta | Ttha | Dda | Ddha | Ta | Tha | Da
+            | Dha | Pa | Pha | Ba | Bha => Manner::Sparsha,
+            Nga | Nya | Nna | Na | Ma => Manner::Anunasika,
+            Ya | Ra | La | Va | Lla => Manner::Antahstha,
+            Sha | Ssa | Sa | Ha => Manner::Ushman,
+            Anusvara | Visarga | Jihvamuliya | Upadhmaniya => Manner::Ayogavaha,
+        }
+    }
+}
+
+/// Classifies the given SLP1 character as a Sanskrit sound.
+///
+/// This returns `None` for non-Sanskrit characters and for characters like the *avagraha* that
+/// are Sanskrit but not sounds.
+pub fn classify(c: char) -> Option<Sound> {
+    use Sound::*;
+    let sound = match c {
+        'a' => A,
+        'A' => Aa,
+        'i' => I,
+        'I' => Ii,
+        'u' => U,
+        'U' => Uu,
+        'f' => Ri,
+        'F' => Rii,
+        'x' => Li,
+        'X' => Lii,
+        'e' => E,
+        'E' => Ai,
+        'o' => O,
+        'O' => Au,
+        'M' => Anusvara,
+        'H' => Visarga,
+        'Z' => Jihvamuliya,
+        'V' => Upadhmaniya,
+        'k' => Ka,
+        'K' => Kha,
+        'g' => Ga,
+        'G' => Gha,
+        'N' => Nga,
+        'c' => Ca,
+        'C' => Cha,
+        'j' => Ja,
+        'J' => Jha,
+        'Y' => Nya,
+        'w' => Tta,
+        'W' => Ttha,
+        'q' => Dda,
+        'Q' => Ddha,
+        'R' => Nna,
+        't' => Ta,
+        'T' => Tha,
+        'd' => Da,
+        'D' => Dha,
+        'n' => Na,
+        'p' => Pa,
+        'P' => Pha,
+        'b' => Ba,
+        'B' => Bha,
+        'm' => Ma,
+        'y' => Ya,
+        'r' => Ra,
+        'l' => La,
+        'v' => Va,
+        'S' => Sha,
+        'z' => Ssa,
+        's' => Sa,
+        'h' => Ha,
+        'L' => Lla,
+        _ => return None,
+    };
+    Some(sound)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_is_sanskrit() {
-        for c in "aAiIuUfFxXeEoOMHkKgGNcCjJYwWqQRtTdDnpPbBmyrlvSzshL'".chars() {
+        for c in "aAiIuUfFxXeEoOMHZVkKgGNcCjJYwWqQRtTdDnpPbBmyrlvSzshL'".chars() {
             assert!(is_sanskrit(c));
         }
         for c in "0123456789,.![]|".chars() {
@@ -54,6 +487,20 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_is_sanskrit_at() {
+        assert!(is_sanskrit_at("o3m", 0));
+        assert!(is_sanskrit_at("o3m", 1));
+        assert!(is_sanskrit_at("o3m", 2));
+        assert!(!is_sanskrit_at("o3m", 3));
+
+        // `3` is a pluta marker only after a vowel.
+        assert!(!is_sanskrit_at("13", 1));
+        assert!(!is_sanskrit_at("k3", 1));
+        assert!(!is_sanskrit_at("|| 3 ||", 3));
+        assert!(!is_sanskrit_at("3", 0));
+    }
+
     #[test]
     fn test_is_ac() {
         for c in "aAiIuUfFxXeEoO".chars() {
@@ -66,11 +513,168 @@ mod tests {
 
     #[test]
     fn test_is_ghosha() {
-        for c in "aAiIuUfFxXeEoOgGnjJYqQRdDnbBmyrlvh".chars() {
+        for c in "aAiIuUfFxXeEoOgGnjJYqQRdDnbBmyrlvhL".chars() {
             assert!(is_ghosha(c));
         }
         for c in "kKcCwWtTpPSzs".chars() {
             assert!(!is_ghosha(c));
         }
     }
+
+    #[test]
+    fn test_chunk() {
+        let text = "rAmaH 12 gacCati. vana45Pala  x";
+        let chunks: Vec<(&str, bool)> = chunk(text)
+            .iter()
+            .map(|c| (&text[c.span.clone()], c.is_sanskrit))
+            .collect();
+        assert_eq!(
+            chunks,
+            vec![
+                ("rAmaH", true),
+                ("12", false),
+                ("gacCati", true),
+                (".", false),
+                ("vana", true),
+                ("45", false),
+                ("Pala", true),
+                ("x", true),
+            ]
+        );
+
+        let text = "o3m iti 13 || 3 ||";
+        let chunks: Vec<(&str, bool)> = chunk(text)
+            .iter()
+            .map(|c| (&text[c.span.clone()], c.is_sanskrit))
+            .collect();
+        assert_eq!(
+            chunks,
+            vec![
+                ("o3m", true),
+                ("iti", true),
+                ("13", false),
+                ("||", false),
+                ("3", false),
+                ("||", false),
+            ]
+        );
+
+        assert!(chunk("").is_empty());
+        assert!(chunk("  ").is_empty());
+        assert_eq!(
+            chunk("अ a"),
+            vec![
+                Chunk {
+                    span: 0..3,
+                    is_sanskrit: false
+                },
+                Chunk {
+                    span: 4..5,
+                    is_sanskrit: true
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_syllable_morae() {
+        assert_eq!(syllable_morae("rAma"), vec![2, 1]);
+        // Closed by a final consonant.
+        assert_eq!(syllable_morae("jagat"), vec![1, 2]);
+        // Anusvara and visarga.
+        assert_eq!(syllable_morae("vanaM gacCa"), vec![1, 2, 2, 1]);
+        assert_eq!(syllable_morae("rAmaH"), vec![2, 2]);
+        // A cluster across a word boundary.
+        assert_eq!(syllable_morae("ca smftam"), vec![2, 1, 2]);
+        // Pluta.
+        assert_eq!(syllable_morae("o3m"), vec![3]);
+        assert!(syllable_morae("").is_empty());
+    }
+
+    #[test]
+    fn test_syllable_and_mora_count() {
+        // Bhagavad Gita 1.1
+        let pada = "Darmakzetre kurukzetre";
+        assert_eq!(syllable_count(pada), 8);
+        assert_eq!(mora_count(pada), 15);
+
+        let pada = "samavetA yuyutsavaH |";
+        assert_eq!(syllable_count(pada), 8);
+        assert_eq!(mora_count(pada), 12);
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify('a'), Some(Sound::A));
+        assert_eq!(classify('F'), Some(Sound::Rii));
+        assert_eq!(classify('K'), Some(Sound::Kha));
+        assert_eq!(classify('z'), Some(Sound::Ssa));
+        assert_eq!(classify('H'), Some(Sound::Visarga));
+        for c in "'30 |".chars() {
+            assert_eq!(classify(c), None);
+        }
+
+        // Every SLP1 sound round-trips.
+        for c in "aAiIuUfFxXeEoOMHZVkKgGNcCjJYwWqQRtTdDnpPbBmyrlvSzshL".chars() {
+            assert_eq!(classify(c).map(|s| s.to_char()), Some(c));
+        }
+    }
+
+    #[test]
+    fn test_manner() {
+        for (chars, manner) in [
+            ("aAiIuUfFxXeEoO", Manner::Svara),
+            ("kKgGcCjJwWqQtTdDpPbB", Manner::Sparsha),
+            ("NYRnm", Manner::Anunasika),
+            ("yrlvL", Manner::Antahstha),
+            ("Szsh", Manner::Ushman),
+            ("MHZV", Manner::Ayogavaha),
+        ] {
+            for c in chars.chars() {
+                assert_eq!(classify(c).unwrap().manner(), manner, "{c}");
+                assert_eq!(classify(c).unwrap().is_vowel(), manner == Manner::Svara);
+            }
+        }
+    }
+
+    #[test]
+    fn test_visarga_family() {
+        // SLP1 has no separate symbol for the ardhavisarga, which is a variant of `Z` and `V`.
+        for (c, sound, place) in [
+            ('H', Sound::Visarga, Place::Kantha),
+            ('Z', Sound::Jihvamuliya, Place::Jihvamula),
+            ('V', Sound::Upadhmaniya, Place::Oshtha),
+        ] {
+            assert!(is_sanskrit(c));
+            assert!(!is_ac(c));
+            assert!(!is_ghosha(c));
+            assert_eq!(classify(c), Some(sound));
+            assert_eq!(sound.place(), place);
+        }
+    }
+
+    #[test]
+    fn test_sound_properties() {
+        let sound = |c| classify(c).unwrap();
+
+        assert!(sound('a').is_vowel());
+        assert!(sound('O').is_vowel());
+        assert!(!sound('k').is_vowel());
+        assert!(!sound('y').is_vowel());
+
+        assert!(sound('g').is_voiced());
+        assert!(sound('h').is_voiced());
+        assert!(sound('L').is_voiced());
+        assert!(!sound('k').is_voiced());
+        assert!(!sound('S').is_voiced());
+
+        assert_eq!(sound('k').place(), Place::Kantha);
+        assert_eq!(sound('S').place(), Place::Talu);
+        assert_eq!(sound('R').place(), Place::Murdha);
+        assert_eq!(sound('s').place(), Place::Danta);
+        assert_eq!(sound('m').place(), Place::Oshtha);
+        assert_eq!(sound('E').place(), Place::KanthaTalu);
+        assert_eq!(sound('o').place(), Place::KanthaOshtha);
+        assert_eq!(sound('v').place(), Place::DantaOshtha);
+    }
 }
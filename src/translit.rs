@@ -3,6 +3,7 @@
 //! DCS data is encoded in IAST, but Vidyut generally prefers SLP1. This module
 //! uses an (unoptimized, untested) transliteration function to convert IAST to SLP1.
 use std::cmp;
+use unicode_normalization::UnicodeNormalization;
 
 fn map_char(cur: &str) -> Option<&'static str> {
     let val = match cur {
@@ -17,6 +18,8 @@ fn map_char(cur: &str) -> Option<&'static str> {
         "au" => "O",
         "ṃ" => "M",
         "ḥ" => "H",
+        "ẖ" => "Z",
+        "ḫ" => "V",
         "ṅ" => "N",
         "kh" => "K",
         "gh" => "G",
@@ -40,9 +43,35 @@ fn map_char(cur: &str) -> Option<&'static str> {
     Some(val)
 }
 
+/// Returns whether `c` is a Latin letter that IAST uses without a diacritic.
+///
+/// IAST has no `f`, `q`, `w`, `x`, or `z`, so these letters always come from some other text.
+fn is_plain_iast(c: char) -> bool {
+    "abcdeghijklmnoprstuvy".contains(c)
+}
+
+/// Returns whether `c` is an IAST letter with a diacritic, e.g. `ā` or `ṣ`.
+fn has_iast_diacritic(c: char) -> bool {
+    let lower: String = c.to_lowercase().collect();
+    !lower.is_ascii() && map_char(&lower).is_some()
+}
+
 /// Hackily transliterate from IAST to SLP1.
+///
+/// Whitespace, including newlines and tabs, is preserved exactly.
+///
+/// IAST marks vowel length with diacritics, not case, so we ignore case when we look up IAST
+/// characters. Otherwise, a capitalized name like `Rāma` would be misread as SLP1 `R` (*ṇa*).
+/// Characters that IAST doesn't use, such as digits, `w`, or non-Latin letters, pass through with
+/// their original case. Plain Latin text is still read as IAST, so digraphs like `th` become
+/// aspirates. For text that mixes IAST with English, use `to_slp1_mixed` instead.
+///
+/// Our mapping uses precomposed (NFC) characters, so we also convert the input to NFC. Otherwise,
+/// decomposed input like `a` + U+0304 (common on macOS) would pass through unchanged.
 pub fn to_slp1(input: &str) -> String {
-    let chars: Vec<char> = input.chars().collect();
+    let chars: Vec<char> = input.nfc().collect();
+    let lower = |cs: &[char]| -> String { cs.iter().flat_map(|c| c.to_lowercase()).collect() };
+
     let mut ret = String::new();
     let mut i = 0;
     while i < chars.len() {
@@ -53,10 +82,9 @@ pub fn to_slp1(input: &str) -> String {
         // so search up to length 2. Start with 2 first so that we match greedily.
         for j in [2, 1] {
             let limit = cmp::min(i + j, chars.len());
-            let cur = String::from_iter(&chars[i..limit]);
             offset = limit - i;
 
-            next = map_char(cur.as_str());
+            next = map_char(&lower(&chars[i..limit]));
             if let Some(_s) = next {
                 break;
             }
@@ -68,8 +96,13 @@ pub fn to_slp1(input: &str) -> String {
                 i += offset;
             }
             None => {
-                // Use the original character as-is.
-                ret += &String::from_iter(&chars[i..=i]);
+                let cur = lower(&chars[i..=i]);
+                if cur.chars().all(is_plain_iast) {
+                    ret += &cur;
+                } else {
+                    // Use the original character as-is.
+                    ret.push(chars[i]);
+                }
                 i += 1;
             }
         }
@@ -77,6 +110,71 @@ pub fn to_slp1(input: &str) -> String {
     ret
 }
 
+/// Transliterates the IAST words in `input` to SLP1 and leaves all other text unchanged.
+///
+/// Use this function for text that mixes IAST with English or another language written in the
+/// Latin alphabet. A word is a run of non-whitespace characters, and we treat a word as IAST only
+/// if it contains at least one IAST letter with a diacritic, such as `ā` or `ṣ`. Such words are
+/// converted as in `to_slp1`. All other words, including Sanskrit words that happen to need no
+/// diacritics (like `dharma`), pass through unchanged.
+pub fn to_slp1_mixed(input: &str) -> String {
+    let input: String = input.nfc().collect();
+    let mut ret = String::new();
+    let mut word_start = None;
+    for (i, c) in input.char_indices() {
+        match (c.is_whitespace(), word_start) {
+            (true, Some(start)) => {
+                ret += &convert_if_iast(&input[start..i]);
+                ret.push(c);
+                word_start = None;
+            }
+            (true, None) => ret.push(c),
+            (false, None) => word_start = Some(i),
+            (false, Some(_)) => (),
+        }
+    }
+    if let Some(start) = word_start {
+        ret += &convert_if_iast(&input[start..]);
+    }
+    ret
+}
+
+/// Converts `word` to SLP1 if it looks like IAST. For details, see `to_slp1_mixed`.
+fn convert_if_iast(word: &str) -> String {
+    if word.chars().any(has_iast_diacritic) {
+        to_slp1(word)
+    } else {
+        word.to_string()
+    }
+}
+
+/// Converts IAST text to a slug that is safe to use in a URL path without escaping.
+///
+/// The slug is the SLP1 form of the text with words joined by `-`. SLP1 is plain ASCII but
+/// distinguishes every sound, so `rāma` and `rama` produce different slugs (`rAma` and `rama`).
+/// As a result, slugs are case-sensitive.
+///
+/// To keep slugs stable and readable, we make these simplifications:
+///
+/// - We drop any character that is not an ASCII letter or digit, such as punctuation, the
+///   avagraha, or unmapped non-ASCII characters.
+/// - We join words with a single `-` and trim any `-` at the start or end of the slug.
+pub fn to_url_slug(input: &str) -> String {
+    let slp1 = to_slp1(input);
+    let mut ret = String::new();
+    for word in slp1.split(|c: char| c.is_whitespace() || c == '-') {
+        let word: String = word.chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+        if word.is_empty() {
+            continue;
+        }
+        if !ret.is_empty() {
+            ret.push('-');
+        }
+        ret += &word;
+    }
+    ret
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -95,4 +193,103 @@ mod tests {
 
         assert_eq!(to_slp1("vāgarthāviva saṃpṛktau"), "vAgarTAviva saMpfktO");
     }
+
+    #[test]
+    fn test_to_slp1_with_avagraha() {
+        // IAST and SLP1 both write the avagraha as an apostrophe.
+        assert_eq!(to_slp1("te 'bruvan"), "te 'bruvan");
+        assert_eq!(to_slp1("so 'ham"), "so 'ham");
+    }
+
+    #[test]
+    fn test_to_slp1_with_jihvamuliya_and_upadhmaniya() {
+        assert_eq!(to_slp1("ẖ ḫ"), "Z V");
+        assert_eq!(to_slp1("antaẖkaraṇam"), "antaZkaraRam");
+        assert_eq!(to_slp1("punaḫpunaḥ"), "punaVpunaH");
+    }
+
+    #[test]
+    fn test_to_slp1_with_capitals() {
+        assert_eq!(to_slp1("Rāma"), "rAma");
+        assert_eq!(to_slp1("ŚIVA"), "Siva");
+        assert_eq!(to_slp1("Rāma went to Himālaya"), "rAma went to himAlaya");
+
+        // Characters outside of IAST keep their case.
+        assert_eq!(to_slp1("Wow"), "Wow");
+        assert_eq!(to_slp1("Ω ω"), "Ω ω");
+        assert_eq!(to_slp1("Kṛṣṇa 1X"), "kfzRa 1X");
+    }
+
+    #[test]
+    fn test_to_slp1_mixed() {
+        assert_eq!(
+            to_slp1_mixed("Rama went to the Himālaya"),
+            "Rama went to the himAlaya"
+        );
+        assert_eq!(
+            to_slp1_mixed("Rāma went to the Himālaya"),
+            "rAma went to the himAlaya"
+        );
+        assert_eq!(
+            to_slp1_mixed("  The Bhagavad Gītā\n\tsays: dharmakṣetre kurukṣetre "),
+            "  The Bhagavad gItA\n\tsays: Darmakzetre kurukzetre "
+        );
+        assert_eq!(to_slp1_mixed(""), "");
+
+        // NFD input is still recognized as IAST.
+        let nfd: String = "śiva".nfd().collect();
+        assert_eq!(to_slp1_mixed(&nfd), "Siva");
+    }
+
+    #[test]
+    fn test_to_slp1_with_nfd_input() {
+        let nfc = "vāgarthāviva saṃpṛktau ḹ";
+        let nfd: String = nfc.nfd().collect();
+        assert_ne!(nfc, nfd);
+        assert_eq!(to_slp1(&nfd), to_slp1(nfc));
+        assert_eq!(to_slp1("s\u{0323}a\u{0304}"), "zA");
+    }
+
+    #[test]
+    fn test_to_slp1_preserves_whitespace() {
+        let iast = "  dharmakṣetre kurukṣetre\n\tsamavetā  yuyutsavaḥ \r\n";
+        let slp1 = "  Darmakzetre kurukzetre\n\tsamavetA  yuyutsavaH \r\n";
+        assert_eq!(to_slp1(iast), slp1);
+    }
+
+    #[test]
+    fn test_to_slp1_with_unusual_input() {
+        // Unmapped characters, including orphaned combining marks, pass through unchanged.
+        assert_eq!(to_slp1(""), "");
+        assert_eq!(to_slp1("\u{0304}"), "\u{0304}");
+        assert_eq!(to_slp1("\u{0323}h"), "\u{0323}h");
+        assert_eq!(to_slp1("ā\u{0304}\u{0304}"), "A\u{0304}\u{0304}");
+        assert_eq!(to_slp1("h"), "h");
+        assert_eq!(to_slp1("kह्"), "kह्");
+    }
+
+    #[test]
+    fn test_to_url_slug() {
+        assert_eq!(
+            to_url_slug("dharmakṣetre kurukṣetre samavetā yuyutsavaḥ |"),
+            "Darmakzetre-kurukzetre-samavetA-yuyutsavaH"
+        );
+        assert_eq!(
+            to_url_slug("vāgarthāv iva saṃpṛktau"),
+            "vAgarTAv-iva-saMpfktO"
+        );
+        assert_eq!(
+            to_url_slug("  oṃ namaḥ śivāya || 1 ||  "),
+            "oM-namaH-SivAya-1"
+        );
+        assert_eq!(to_url_slug("so 'ham"), "so-ham");
+        assert_eq!(to_url_slug(""), "");
+    }
+
+    #[test]
+    fn test_to_url_slug_keeps_distinctions() {
+        assert_ne!(to_url_slug("rāma"), to_url_slug("rama"));
+        assert_ne!(to_url_slug("śiva"), to_url_slug("ṣiva"));
+        assert_eq!(to_url_slug("Rāma"), to_url_slug("rāma"));
+    }
 }
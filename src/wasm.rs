@@ -0,0 +1,58 @@
+//! WebAssembly bindings for the segmenter.
+//!
+//! Since WebAssembly has no filesystem, callers pass in the raw bytes of each data file. These
+//! are the same files that `Config` points to on disk.
+use crate::sandhi::Sandhi;
+use crate::scoring::Model;
+use crate::segmenting::Segmenter;
+use vidyut_kosha::Kosha;
+use wasm_bindgen::prelude::*;
+
+/// A `Segmenter` that can be used from JavaScript.
+#[wasm_bindgen]
+pub struct WasmSegmenter {
+    segmenter: Segmenter,
+}
+
+#[wasm_bindgen]
+impl WasmSegmenter {
+    /// Creates a segmenter from the contents of its data files.
+    ///
+    /// - `sandhi_rules` -- the contents of `sandhi-rules.csv`.
+    /// - `padas`, `pratipadikas`, `dhatus` -- the contents of `padas.fst`, `pratipadikas.csv`,
+    ///   and `dhatus.csv` in the lexicon directory.
+    /// - `lemma_counts`, `transitions` -- the contents of `lemma-counts.csv` and
+    ///   `transitions.csv` in the model directory.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        sandhi_rules: &[u8],
+        padas: Vec<u8>,
+        pratipadikas: &[u8],
+        dhatus: &[u8],
+        lemma_counts: &[u8],
+        transitions: &[u8],
+    ) -> Result<WasmSegmenter, JsError> {
+        let to_js = |e: Box<dyn std::error::Error>| JsError::new(&e.to_string());
+        let sandhi = Sandhi::from_reader(sandhi_rules).map_err(to_js)?;
+        let lexicon = Kosha::from_bytes(padas, pratipadikas, dhatus).map_err(to_js)?;
+        let model = Model::from_bytes(lemma_counts, transitions).map_err(to_js)?;
+        Ok(WasmSegmenter {
+            segmenter: Segmenter::from_data(sandhi, lexicon, model),
+        })
+    }
+
+    /// Segments the given SLP1 text.
+    ///
+    /// Returns an array of objects with the same fields as the serialized form of `Word`:
+    ///
+    /// - `text` -- the word's text.
+    /// - `lemma` -- the word's root or stem.
+    /// - `pos` -- the word's part of speech as a short tag (e.g. `s` for *subanta*).
+    /// - `is_case_folded` -- whether we found the word only by lowercasing it.
+    /// - `boundary` -- how the word was separated from the previous word (`space`, `join`, or
+    ///   `sandhi`), or `undefined` for the first word.
+    pub fn segment(&self, text: &str) -> Result<JsValue, JsValue> {
+        let words = self.segmenter.segment(text);
+        Ok(serde_wasm_bindgen::to_value(&words)?)
+    }
+}
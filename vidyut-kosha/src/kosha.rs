@@ -33,10 +33,10 @@
 //! million words in around 31MB of data with an average storage cost of 1 byte per word. Of
 //! course, the specific storage cost will vary depending on the words in the input list.
 use crate::packing::*;
-use crate::semantics::Pada;
+use crate::semantics::{POSTag, Pada};
 use fst::map::Stream;
 use fst::raw::{Fst, Node, Output};
-use fst::{Map, MapBuilder};
+use fst::{Map, MapBuilder, Streamer};
 use log::info;
 use std::collections::HashMap;
 use std::error::Error;
@@ -79,10 +79,27 @@ fn to_packed_pada(output: Output) -> PackedPada {
     PackedPada::from_u32(output.value() as u32)
 }
 
+/// The bytes of an FST, either on the heap or memory-mapped from a file.
+enum FstData {
+    Heap(Vec<u8>),
+    #[cfg(feature = "mmap")]
+    Mmap(memmap2::Mmap),
+}
+
+impl AsRef<[u8]> for FstData {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            FstData::Heap(v) => v,
+            #[cfg(feature = "mmap")]
+            FstData::Mmap(m) => m,
+        }
+    }
+}
+
 /// A highly memory-efficient Sanskrit lexicon.
 pub struct Kosha {
     /// The underlying FST object.
-    fst: Map<Vec<u8>>,
+    fst: Map<FstData>,
     /// Maps indices to semantics objects.
     unpacker: Unpacker,
 }
@@ -93,7 +110,34 @@ impl Kosha {
         let paths = Paths::new(base_path);
 
         info!("Loading fst from `{:?}`", paths.fst());
-        let fst = Map::new(std::fs::read(paths.fst())?)?;
+        let fst = Map::new(FstData::Heap(std::fs::read(paths.fst())?))?;
+        let unpacker = Unpacker::from_data(
+            PratipadikaTable::read(&paths.pratipadikas())?,
+            DhatuTable::read(&paths.dhatus())?,
+        );
+
+        Ok(Self { fst, unpacker })
+    }
+
+    /// Reads the lexicon from the given `base_path` by memory-mapping its FST.
+    ///
+    /// Unlike `new`, this constructor doesn't copy the FST onto the heap. Instead, the OS loads
+    /// pages of the FST file on demand, and processes that map the same file share a single copy
+    /// of it in the page cache. The small `Dhatu` and `Pratipadika` tables are still read onto the
+    /// heap.
+    ///
+    /// # Safety
+    ///
+    /// The FST file must not be modified or truncated while the returned `Kosha` exists. Doing so
+    /// is undefined behavior, since the lexicon reads the file's bytes directly from memory.
+    #[cfg(feature = "mmap")]
+    pub unsafe fn from_mmap(base_path: &Path) -> Result<Self, Box<dyn Error>> {
+        let paths = Paths::new(base_path);
+
+        info!("Memory-mapping fst from `{:?}`", paths.fst());
+        let file = File::open(paths.fst())?;
+        let mmap = memmap2::Mmap::map(&file)?;
+        let fst = Map::new(FstData::Mmap(mmap))?;
         let unpacker = Unpacker::from_data(
             PratipadikaTable::read(&paths.pratipadikas())?,
             DhatuTable::read(&paths.dhatus())?,
@@ -102,6 +146,25 @@ impl Kosha {
         Ok(Self { fst, unpacker })
     }
 
+    /// Creates the lexicon from in-memory data.
+    ///
+    /// Each argument should have the same contents as the corresponding file that `Builder`
+    /// writes to disk: `fst` is the contents of `padas.fst`, `pratipadikas` is the contents of
+    /// `pratipadikas.csv`, and `dhatus` is the contents of `dhatus.csv`. Use this constructor in
+    /// environments that embed the lexicon in the binary or that lack a writable filesystem.
+    pub fn from_bytes(
+        fst: impl Into<Vec<u8>>,
+        pratipadikas: &[u8],
+        dhatus: &[u8],
+    ) -> Result<Self, Box<dyn Error>> {
+        let fst = Map::new(FstData::Heap(fst.into()))?;
+        let unpacker = Unpacker::from_data(
+            PratipadikaTable::from_reader(pratipadikas)?,
+            DhatuTable::from_reader(dhatus)?,
+        );
+        Ok(Self { fst, unpacker })
+    }
+
     /// Returns whether this lexicon contains at least one word with exact value `key`.
     #[inline]
     pub fn contains_key(&self, key: &str) -> bool {
@@ -135,6 +198,13 @@ impl Kosha {
         self.unpacker.unpack(p)
     }
 
+    /// Iterates over the lemmas of all *pratipadika*s and *dhātu*s in this lexicon.
+    ///
+    /// A lemma may appear more than once.
+    pub fn lemmas(&self) -> impl Iterator<Item = String> + '_ {
+        self.unpacker.lemmas()
+    }
+
     /// Gets all results for the given `key`, including duplicates.
     #[inline]
     pub fn get_all(&self, key: &str) -> Vec<PackedPada> {
@@ -168,6 +238,74 @@ impl Kosha {
     pub fn stream(&self) -> Stream<'_> {
         self.fst.stream()
     }
+
+    /// Returns the length in bytes of the longest key in this lexicon.
+    ///
+    /// The lexicon doesn't store this value, so this method scans the entire FST.
+    pub fn max_key_len(&self) -> usize {
+        let mut ret = 0;
+        let mut stream = self.fst.stream();
+        while let Some((key, _)) = stream.next() {
+            ret = ret.max(self.base_key(key).len());
+        }
+        ret
+    }
+
+    /// Returns whether `key` is an extended key created by `create_extended_key`.
+    fn is_extended_key(&self, key: &[u8]) -> bool {
+        let n = key.len();
+        n > 2
+            && key[n - 2..].iter().all(|&b| b <= DUPES_PER_BYTE)
+            && std::str::from_utf8(&key[..n - 2]).is_ok_and(|k| self.contains_key(k))
+    }
+
+    /// Returns `key` without the extension added by `create_extended_key`, if any.
+    fn base_key<'a>(&self, key: &'a [u8]) -> &'a [u8] {
+        if self.is_extended_key(key) {
+            &key[..key.len() - 2]
+        } else {
+            key
+        }
+    }
+
+    /// Counts the entries in this lexicon by part of speech.
+    ///
+    /// A key with several analyses, such as *gacCati*, contributes one entry per analysis.
+    pub fn pos_counts(&self) -> HashMap<POSTag, usize> {
+        let mut counts = HashMap::new();
+        let mut stream = self.fst.stream();
+        while let Some((_, value)) = stream.next() {
+            let packed = PackedPada::from_u32(value as u32);
+            *counts.entry(packed.pos_tag()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Returns all words in this lexicon whose semantics are exactly `pada`, in time linear in
+    /// the size of the lexicon.
+    ///
+    /// This is the reverse of `get_all`: given the semantics of a word, such as *gam* in the
+    /// third-person singular *laṭ*, it returns the word's surface forms, such as `gacCati`.
+    /// Since `pada` must match exactly, it must also match details like the *liṅga*s listed on
+    /// a *prātipadika*.
+    ///
+    /// The lexicon isn't indexed by semantics, so this method scans the entire FST on every
+    /// call. For generating many forms, consider using `vidyut-prakriya` instead.
+    pub fn inflect(&self, pada: &Pada) -> Vec<String> {
+        let mut ret = Vec::new();
+        let mut stream = self.fst.stream();
+        while let Some((key, value)) = stream.next() {
+            let packed = PackedPada::from_u32(value as u32);
+            match self.unpack(&packed) {
+                Ok(p) if p == *pada => (),
+                _ => continue,
+            }
+            if let Ok(text) = std::str::from_utf8(self.base_key(key)) {
+                ret.push(text.to_string());
+            }
+        }
+        ret
+    }
 }
 
 /// Appends all available duplicates to our list of results.
@@ -177,7 +315,7 @@ impl Kosha {
 /// - `out`: the output corresponding to this state.
 /// - `fst`: the underlying FST.
 /// - `results`: the results list.
-fn add_duplicates(node: Node, out: Output, fst: &Fst<Vec<u8>>, results: &mut Vec<PackedPada>) {
+fn add_duplicates(node: Node, out: Output, fst: &Fst<FstData>, results: &mut Vec<PackedPada>) {
     for c1 in 0..=DUPES_PER_BYTE {
         if let Some(i1) = node.find_input(c1) {
             let t1 = node.transition(i1);
@@ -288,7 +426,7 @@ impl Builder {
 
         info!("Reading new FST from `{:?}`.", self.paths.base);
         let fst_data = std::fs::read(self.paths.fst())?;
-        let fst = Map::new(fst_data)?;
+        let fst = Map::new(FstData::Heap(fst_data))?;
         Ok(Kosha { fst, unpacker })
     }
 }
@@ -303,6 +441,56 @@ mod tests {
 
     type TestResult = Result<(), Box<dyn Error>>;
 
+    /// Returns the *tiṅanta* `gacCati`.
+    fn gacchati() -> Pada {
+        Pada::Tinanta(Tinanta {
+            dhatu: Dhatu("gam".to_string()),
+            purusha: Purusha::Prathama,
+            vacana: Vacana::Eka,
+            lakara: Lakara::Lat,
+            pada: PadaPrayoga::Parasmaipada,
+        })
+    }
+
+    /// Returns the *subanta* `agnim`.
+    fn agnim() -> Pada {
+        Pada::Subanta(Subanta {
+            pratipadika: Pratipadika::Basic {
+                text: "agni".to_string(),
+                lingas: vec![Linga::Pum],
+            },
+            linga: Linga::Pum,
+            vacana: Vacana::Eka,
+            vibhakti: Vibhakti::V2,
+            is_purvapada: false,
+        })
+    }
+
+    /// Returns the *kṛdanta* *gacCat* in the second *vibhakti*, which we store under `gacCati`
+    /// so that our tests have a duplicate key.
+    fn gacchat() -> Pada {
+        Pada::Subanta(Subanta {
+            pratipadika: Pratipadika::Krdanta {
+                dhatu: Dhatu("gam".to_string()),
+                pratyaya: KrtPratyaya::Shatr,
+            },
+            linga: Linga::Pum,
+            vacana: Vacana::Eka,
+            vibhakti: Vibhakti::V2,
+            is_purvapada: false,
+        })
+    }
+
+    /// Writes a lexicon with the given entries to `dir`. Keys must be in lexicographic order.
+    fn build_lexicon(dir: &Path, entries: &[(&str, &Pada)]) -> TestResult {
+        let mut builder = Builder::new(dir)?;
+        for (key, pada) in entries {
+            builder.insert(key, pada)?;
+        }
+        builder.into_lexicon()?;
+        Ok(())
+    }
+
     #[test]
     fn test_paths() {
         let paths = Paths {
@@ -398,6 +586,143 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_inflect() -> TestResult {
+        let tin = gacchati();
+        let krdanta = gacchat();
+        let sup = agnim();
+
+        let dir = tempdir()?;
+        build_lexicon(
+            dir.path(),
+            &[("agnim", &sup), ("gacCati", &tin), ("gacCati", &krdanta)],
+        )?;
+        let lex = Kosha::new(dir.path())?;
+
+        assert_eq!(lex.inflect(&sup), vec!["agnim"]);
+        assert_eq!(lex.inflect(&tin), vec!["gacCati"]);
+        // `krdanta` is stored under an extended key, but we should return the base key.
+        assert_eq!(lex.inflect(&krdanta), vec!["gacCati"]);
+
+        // gacCanti
+        let missing = Pada::Tinanta(Tinanta {
+            dhatu: Dhatu("gam".to_string()),
+            purusha: Purusha::Prathama,
+            vacana: Vacana::Bahu,
+            lakara: Lakara::Lat,
+            pada: PadaPrayoga::Parasmaipada,
+        });
+        assert!(lex.inflect(&missing).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_pos_counts() -> TestResult {
+        let dir = tempdir()?;
+        build_lexicon(
+            dir.path(),
+            &[
+                ("agnim", &agnim()),
+                ("gacCati", &gacchati()),
+                ("gacCati", &gacchat()),
+            ],
+        )?;
+        let lex = Kosha::new(dir.path())?;
+
+        // The duplicate key `gacCati` contributes one entry per analysis.
+        let counts = lex.pos_counts();
+        assert_eq!(counts.get(&POSTag::Subanta), Some(&2));
+        assert_eq!(counts.get(&POSTag::Tinanta), Some(&1));
+        assert_eq!(counts.get(&POSTag::Avyaya), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_key_len() -> TestResult {
+        let dir = tempdir()?;
+        build_lexicon(
+            dir.path(),
+            &[
+                ("agnim", &agnim()),
+                ("gacCati", &gacchati()),
+                ("gacCati", &gacchat()),
+            ],
+        )?;
+        let lex = Kosha::new(dir.path())?;
+
+        // The extended key for the duplicate `gacCati` is longer, but we should ignore it.
+        assert_eq!(lex.max_key_len(), "gacCati".len());
+        Ok(())
+    }
+
+    #[test]
+    fn test_lemmas() -> TestResult {
+        let dir = tempdir()?;
+        build_lexicon(dir.path(), &[("agnim", &agnim()), ("gacCati", &gacchati())])?;
+        let lex = Kosha::new(dir.path())?;
+
+        let mut lemmas: Vec<String> = lex.lemmas().collect();
+        lemmas.sort();
+        assert_eq!(lemmas, vec!["agni", "gam"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_bytes() -> TestResult {
+        let tin = gacchati();
+        let sup = agnim();
+
+        let dir = tempdir()?;
+        build_lexicon(dir.path(), &[("agnim", &sup), ("gacCati", &tin)])?;
+
+        let paths = Paths::new(dir.path());
+        let lex = Kosha::from_bytes(
+            std::fs::read(paths.fst())?,
+            &std::fs::read(paths.pratipadikas())?,
+            &std::fs::read(paths.dhatus())?,
+        )?;
+
+        assert!(lex.contains_key("agnim"));
+        assert!(lex.contains_prefix("gacC"));
+        let padas: Result<Vec<Pada>, _> = lex
+            .get_all("gacCati")
+            .iter()
+            .map(|p| lex.unpack(p))
+            .collect();
+        assert_eq!(padas?, vec![tin]);
+
+        // Invalid FST data is an error.
+        assert!(Kosha::from_bytes(b"junk".as_slice(), b"", b"").is_err());
+        Ok(())
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_from_mmap() -> TestResult {
+        let tin = gacchati();
+        let sup = agnim();
+
+        let dir = tempdir()?;
+        build_lexicon(
+            dir.path(),
+            &[("agnim", &sup), ("gacCati", &tin), ("gacCati", &sup)],
+        )?;
+
+        let heap = Kosha::new(dir.path())?;
+        // Safe because we don't modify the FST file during this test.
+        let mmap = unsafe { Kosha::from_mmap(dir.path())? };
+        for key in ["agnim", "gacCati", "gacC", "xyz"] {
+            assert_eq!(heap.contains_key(key), mmap.contains_key(key));
+            assert_eq!(heap.contains_prefix(key), mmap.contains_prefix(key));
+            let heap_padas: Result<Vec<Pada>, _> =
+                heap.get_all(key).iter().map(|p| heap.unpack(p)).collect();
+            let mmap_padas: Result<Vec<Pada>, _> =
+                mmap.get_all(key).iter().map(|p| mmap.unpack(p)).collect();
+            assert_eq!(heap_padas?, mmap_padas?);
+        }
+        Ok(())
+    }
+
     #[test]
     fn test_create_extended_key() -> TestResult {
         let cases = vec![
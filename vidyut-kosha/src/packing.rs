@@ -57,11 +57,19 @@ impl DhatuTable {
         self.0.get(index)
     }
 
+    /// Iterates over all `Dhatu`s in this table.
+    pub fn iter(&self) -> impl Iterator<Item = &Dhatu> {
+        self.0.iter()
+    }
+
     /// Reads this table from disk.
     pub fn read(path: &Path) -> Result<Self, Box<dyn Error>> {
         let f = File::open(path)?;
-        let reader = BufReader::new(f);
+        Self::from_reader(BufReader::new(f))
+    }
 
+    /// Reads this table from the given reader, which has the same format as the file on disk.
+    pub fn from_reader(reader: impl BufRead) -> Result<Self, Box<dyn Error>> {
         let mut ret = Vec::new();
         for line in reader.lines() {
             ret.push(Dhatu(line?.to_string()));
@@ -94,11 +102,19 @@ impl PratipadikaTable {
         self.0.get(index)
     }
 
+    /// Iterates over all `Pratipadika`s in this table.
+    pub fn iter(&self) -> impl Iterator<Item = &Pratipadika> {
+        self.0.iter()
+    }
+
     /// Reads this table from disk.
     pub fn read(path: &Path) -> Result<Self, Box<dyn Error>> {
         let f = File::open(path)?;
-        let reader = BufReader::new(f);
+        Self::from_reader(BufReader::new(f))
+    }
 
+    /// Reads this table from the given reader, which has the same format as the file on disk.
+    pub fn from_reader(reader: impl BufRead) -> Result<Self, Box<dyn Error>> {
         let mut ret = Vec::new();
         for line in reader.lines() {
             ret.push(line?.to_string().parse()?);
@@ -258,6 +274,16 @@ impl PackedPada {
         PackedTinanta::from_bytes(self.payload().to_le_bytes())
     }
 
+    /// Returns the part of speech for these semantics without unpacking the rest of the data.
+    pub fn pos_tag(&self) -> POSTag {
+        match self.pos() {
+            PartOfSpeech::None => POSTag::None,
+            PartOfSpeech::Subanta => POSTag::Subanta,
+            PartOfSpeech::Tinanta => POSTag::Tinanta,
+            PartOfSpeech::Avyaya => POSTag::Avyaya,
+        }
+    }
+
     pub fn to_u32(self) -> u32 {
         u32::from_le_bytes(self.into_bytes())
     }
@@ -387,6 +413,13 @@ impl Unpacker {
         }
     }
 
+    /// Iterates over the lemmas of all *pratipadika*s and *dhātu*s known to this unpacker.
+    pub fn lemmas(&self) -> impl Iterator<Item = String> + '_ {
+        let pratipadikas = self.pratipadikas.iter().map(|p| p.lemma());
+        let dhatus = self.dhatus.iter().map(|d| d.0.clone());
+        pratipadikas.chain(dhatus)
+    }
+
     pub fn unpack(&self, pada: &PackedPada) -> Result<Pada, Box<dyn Error>> {
         match pada.pos() {
             PartOfSpeech::Avyaya => pada.unwrap_as_avyaya().unpack(&self.pratipadikas),
@@ -7,7 +7,7 @@ use std::str::FromStr;
 /// The dhatus in the Dhatupatha are organized in ten large *gaṇa*s or classes. These gaṇas
 /// add various properties to the dhatu, most notably the specific *vikaraṇa* (stem suffix) we use
 /// before sarvadhatuka suffixes.
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum Gana {
     /// The first gaṇa, whose first dhatu is `BU`.
     Bhvadi,
@@ -130,7 +130,7 @@ impl From<Gana> for u8 {
 /// is no canonical version of the Dhatupatha, and we cannot expect that a dhatu's index is
 /// consistent across all of these versions. So we thought it better to avoid hard-coding indices
 /// or requiring callers to follow our specific conventions.)
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
 pub enum Antargana {
     /// Antargana of *tud* gana. Pratyayas that follow dhatus in kut-Adi will generally be marked
     /// Nit per 1.2.1. Required because of duplicates like `juqa~`.
@@ -151,7 +151,7 @@ pub enum Antargana {
 ///
 /// For details on what these pratyayas mean and what kinds of words they produce, see the comments
 /// below.
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum Sanadi {
     /// `san`, which creates desiderative roots per 3.1.7.
     ///
@@ -203,7 +203,7 @@ impl FromStr for Sanadi {
 }
 
 /// The verb root to use for the derivation.
-#[derive(Debug)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct Dhatu {
     upadesha: CompactString,
     gana: Gana,
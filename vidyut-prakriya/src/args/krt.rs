@@ -7,7 +7,7 @@ use std::str::FromStr;
 /// case explicitly here so that we can name pratyayas more concisely with SLP1. Doing so helps us
 /// distinguish between pratyayas like `naN` and `nan`.
 #[allow(dead_code, non_camel_case_types)]
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
 pub enum Krt {
     /// -Alu
     Aluc,
@@ -340,6 +340,7 @@ impl FromStr for Krt {
 }
 
 /// The information required to derive a krdanta in the grammar.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct KrdantaArgs {
     krt: Krt,
 }
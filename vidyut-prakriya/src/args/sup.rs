@@ -4,7 +4,7 @@ use crate::tag::Tag;
 use std::str::FromStr;
 
 /// The gender of some subanta.
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum Linga {
     /// The masculine.
     Pum,
@@ -44,7 +44,7 @@ impl FromStr for Linga {
 }
 
 /// The case ending of some subanta.
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum Vibhakti {
     /// The first vibhakti . Sometimes called the *nominative case*.
     Prathama,
@@ -114,6 +114,7 @@ impl FromStr for Vibhakti {
 }
 
 /// The information required to derive a subanta in the grammar.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct SubantaArgs {
     linga: Linga,
     vacana: Vacana,
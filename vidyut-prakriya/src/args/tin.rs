@@ -47,7 +47,7 @@ impl FromStr for Prayoga {
 }
 
 /// The person of some tinanta.
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum Purusha {
     /// The third person.
     Prathama,
@@ -89,7 +89,7 @@ impl FromStr for Purusha {
 }
 
 /// The number of some tinanta or subanta.
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum Vacana {
     /// The singular.
     Eka,
@@ -216,6 +216,43 @@ impl FromStr for Lakara {
     }
 }
 
+/// The pada of some tinanta, which determines the set of endings it uses.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Pada {
+    /// The *parasmaipada* endings, e.g. *Bavati*, *karoti*. Sometimes called the *active voice*.
+    Parasmai,
+    /// The *Atmanepada* endings, e.g. *eDate*, *kurute*. Sometimes called the *middle voice*.
+    Atmane,
+}
+
+impl Pada {
+    pub(crate) fn as_tag(&self) -> Tag {
+        match self {
+            Self::Parasmai => Tag::Parasmaipada,
+            Self::Atmane => Tag::Atmanepada,
+        }
+    }
+    /// Returns a simple human-readable string that represents this enum's value.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Parasmai => "parasmai",
+            Self::Atmane => "atmane",
+        }
+    }
+}
+
+impl FromStr for Pada {
+    type Err = ArgumentError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let res = match s {
+            "parasmai" => Self::Parasmai,
+            "atmane" => Self::Atmane,
+            &_ => return Err(ArgumentError::enum_parse_error("Pada", s)),
+        };
+        Ok(res)
+    }
+}
+
 /// The information required to derive a tinanta in the grammar.
 ///
 /// If a tinanta were just a matter of prayoga/purusha/lakara/vacana, a struct like this would not
@@ -227,11 +264,13 @@ impl FromStr for Lakara {
 ///
 /// Since we want to keep these args manageable and don't want to repeatedly break our main API, we
 /// decided to wrap args in this struct and expose its values through accessors.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct TinantaArgs {
     prayoga: Prayoga,
     purusha: Purusha,
     lakara: Lakara,
     vacana: Vacana,
+    pada: Option<Pada>,
 }
 
 impl TinantaArgs {
@@ -251,6 +290,11 @@ impl TinantaArgs {
     pub fn vacana(&self) -> Vacana {
         self.vacana
     }
+    /// The pada to use in the derivation, if any. If `None`, we allow all padas that the dhatu
+    /// supports.
+    pub fn pada(&self) -> Option<Pada> {
+        self.pada
+    }
 
     /// Returns a new builder for this struct.
     pub fn builder() -> TinantaArgsBuilder {
@@ -265,6 +309,7 @@ pub struct TinantaArgsBuilder {
     purusha: Option<Purusha>,
     lakara: Option<Lakara>,
     vacana: Option<Vacana>,
+    pada: Option<Pada>,
 }
 
 impl TinantaArgsBuilder {
@@ -288,6 +333,14 @@ impl TinantaArgsBuilder {
         self.vacana = Some(val);
         self
     }
+    /// *(optional)* Sets the pada to use in the derivation.
+    ///
+    /// If set, we keep only the prakriyas that use this pada. For example, a dhatu like *qukf\\Y*
+    /// supports both padas, so we can request just *karoti* or just *kurute*.
+    pub fn pada(mut self, val: Pada) -> Self {
+        self.pada = Some(val);
+        self
+    }
 
     /// Helper function for creating error messages.
     fn field_missing(name: &str) -> ArgumentError {
@@ -315,6 +368,7 @@ impl TinantaArgsBuilder {
                 Some(x) => x,
                 _ => return Err(Self::field_missing("vacana")),
             },
+            pada: self.pada,
         })
     }
 }
@@ -5,17 +5,22 @@
 use crate::ac_sandhi;
 use crate::angasya;
 use crate::ardhadhatuka;
-use crate::args::{Dhatu, KrdantaArgs, Lakara, Sanadi, SubantaArgs, TinantaArgs};
+use crate::args::{
+    Dhatu, KrdantaArgs, Lakara, Linga, Prayoga, Purusha, Sanadi, SubantaArgs, TinantaArgs, Vacana,
+    Vibhakti,
+};
 use crate::atidesha;
 use crate::atmanepada;
+use crate::cache::{CacheKey, PrakriyaCache};
 use crate::dhatu_karya;
 use crate::dvitva;
 use crate::it_agama;
 use crate::krt_pratyaya;
 use crate::la_karya;
-use crate::prakriya::Prakriya;
+use crate::prakriya::{Observer, Prakriya, Rule, StepObserver};
 use crate::prakriya_stack::PrakriyaStack;
 use crate::pratipadika_karya;
+use crate::profile::{ProfileReport, Profiler};
 use crate::samjna;
 use crate::samprasarana;
 use crate::sanadi;
@@ -23,10 +28,71 @@ use crate::sup_karya;
 use crate::tin_pratyaya;
 use crate::tripadi;
 use crate::vikarana;
+use compact_str::CompactString;
 use std::error::Error;
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
 
 type Result<T> = std::result::Result<T, Box<dyn Error>>;
 
+/// Keeps only the prakriyas that use the pada requested in `args`, if any.
+fn filter_by_pada(prakriyas: Vec<Prakriya>, args: &TinantaArgs) -> Vec<Prakriya> {
+    match args.pada() {
+        Some(pada) => prakriyas
+            .into_iter()
+            .filter(|p| p.has_tag(pada.as_tag()))
+            .collect(),
+        None => prakriyas,
+    }
+}
+
+/// Returns the distinct final forms of the given prakriyas in sorted order.
+fn to_forms(prakriyas: &[Prakriya]) -> Vec<String> {
+    let mut forms: Vec<_> = prakriyas.iter().map(|p| p.text().to_string()).collect();
+    forms.sort();
+    forms.dedup();
+    forms
+}
+
+/// Locks the derivation cache.
+///
+/// A panic while the lock is held leaves the cache in a consistent state, so we ignore lock
+/// poisoning.
+fn lock(cache: &Mutex<PrakriyaCache>) -> MutexGuard<'_, PrakriyaCache> {
+    cache.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// All purushas, in the order that grammars traditionally list them.
+const PURUSHAS: &[Purusha] = &[Purusha::Prathama, Purusha::Madhyama, Purusha::Uttama];
+
+/// All vacanas, in the order that grammars traditionally list them.
+const VACANAS: &[Vacana] = &[Vacana::Eka, Vacana::Dvi, Vacana::Bahu];
+
+/// All vibhaktis, in the order that grammars traditionally list them.
+const VIBHAKTIS: &[Vibhakti] = &[
+    Vibhakti::Prathama,
+    Vibhakti::Dvitiya,
+    Vibhakti::Trtiya,
+    Vibhakti::Caturthi,
+    Vibhakti::Panchami,
+    Vibhakti::Sasthi,
+    Vibhakti::Saptami,
+    Vibhakti::Sambodhana,
+];
+
+/// Returns an error if a step observer has stopped this prakriya.
+///
+/// Once a prakriya is stopped, `Prakriya::step` is a no-op, so the remaining rules would run for
+/// nothing. We call this between the major stages of a derivation so that we can return early.
+fn check_stopped(p: &Prakriya) -> Result<()> {
+    if p.is_stopped() {
+        Err("derivation stopped by step observer".into())
+    } else {
+        Ok(())
+    }
+}
+
 /// Adds a dhatu to the prakriya.
 fn add_dhatu(p: &mut Prakriya, dhatu: &Dhatu, is_ardhadhatuka: bool) -> Result<()> {
     dhatu_karya::run(p, dhatu)?;
@@ -81,6 +147,7 @@ fn derive_tinanta(p: &mut Prakriya, dhatu: &Dhatu, args: &TinantaArgs) -> Result
     p.add_tags(&[prayoga.as_tag(), purusha.as_tag(), vacana.as_tag()]);
 
     add_dhatu(p, dhatu, lakara.is_ardhadhatuka())?;
+    check_stopped(p)?;
 
     // Add the lakAra and convert it to a basic tin ending.
     la_karya::run(p, lakara)?;
@@ -99,6 +166,7 @@ fn derive_tinanta(p: &mut Prakriya, dhatu: &Dhatu, args: &TinantaArgs) -> Result
     ardhadhatuka::run_before_vikarana(p, lakara);
     vikarana::run(p)?;
     samjna::run(p);
+    check_stopped(p)?;
 
     // --- Code below this line needs to be cleaned up. ---
 
@@ -123,6 +191,7 @@ fn derive_tinanta(p: &mut Prakriya, dhatu: &Dhatu, args: &TinantaArgs) -> Result
     // --- Code above this line needs to be cleaned up. ---
 
     angasya::iit_agama(p);
+    check_stopped(p)?;
 
     finish_prakriya(p);
 
@@ -134,6 +203,7 @@ fn derive_subanta(p: &mut Prakriya, pratipadika: &str, args: &SubantaArgs) -> Re
 
     sup_karya::run(p, args);
     samjna::run(p);
+    check_stopped(p)?;
 
     finish_prakriya(p);
 
@@ -146,7 +216,9 @@ fn derive_krdanta(p: &mut Prakriya, dhatu: &Dhatu, args: &KrdantaArgs) -> Result
     add_dhatu(p, dhatu, krt.is_ardhadhatuka())?;
     krt_pratyaya::run(p, krt);
     samjna::run(p);
+    check_stopped(p)?;
     run_various_dhatu_tasks(p);
+    check_stopped(p)?;
     finish_prakriya(p);
 
     Ok(())
@@ -187,6 +259,10 @@ pub struct Ashtadhyayi {
     // - `disable`  -- if set, disable the rules provided. To implement this, we should make
     //   `Prakriya::step` private and add a check statement with `Prakriya::op`.
     log_steps: bool,
+    step_observer: Option<StepObserver>,
+    profiler: Option<Profiler>,
+    cache: Option<Mutex<PrakriyaCache>>,
+    num_failed_derivations: AtomicUsize,
 }
 
 impl Default for Ashtadhyayi {
@@ -199,7 +275,13 @@ impl Default for Ashtadhyayi {
 impl Ashtadhyayi {
     /// Creates an interface with sane defaults.
     pub fn new() -> Self {
-        Ashtadhyayi { log_steps: true }
+        Ashtadhyayi {
+            log_steps: true,
+            step_observer: None,
+            profiler: None,
+            cache: None,
+            num_failed_derivations: AtomicUsize::new(0),
+        }
     }
 
     /// Returns a builder that exposes configuration options for how the engine runs rules and
@@ -230,10 +312,12 @@ impl Ashtadhyayi {
     /// # Ok::<(), ArgumentError>(())
     /// ```
     pub fn derive_tinantas(&self, dhatu: &Dhatu, args: &TinantaArgs) -> Vec<Prakriya> {
-        let mut stack = PrakriyaStack::new();
-        // TODO: handle error properly.
-        stack.find_all(|p| derive_tinanta(p, dhatu, args), self.log_steps);
-        stack.prakriyas()
+        let key = || CacheKey::Tinanta(dhatu.clone(), args.clone());
+        self.with_cache(key, || {
+            let (prakriyas, is_complete) =
+                self.find_all(|p| derive_tinanta(p, dhatu, args), self.log_steps);
+            (filter_by_pada(prakriyas, args), is_complete)
+        })
     }
 
     /// Returns all possible tinanta prakriyas that can be derived with the given initial
@@ -256,9 +340,10 @@ impl Ashtadhyayi {
     /// # Ok::<(), ArgumentError>(())
     /// ```
     pub fn derive_subantas(&self, pratipadika: &str, args: &SubantaArgs) -> Vec<Prakriya> {
-        let mut stack = PrakriyaStack::new();
-        stack.find_all(|p| derive_subanta(p, pratipadika, args), self.log_steps);
-        stack.prakriyas()
+        let key = || CacheKey::Subanta(CompactString::from(pratipadika), args.clone());
+        self.with_cache(key, || {
+            self.find_all(|p| derive_subanta(p, pratipadika, args), self.log_steps)
+        })
     }
 
     /// Returns all possible krdanta prakriyas that can be derived with the given initial
@@ -280,9 +365,265 @@ impl Ashtadhyayi {
     /// # Ok::<(), ArgumentError>(())
     /// ```
     pub fn derive_krdantas(&self, dhatu: &Dhatu, args: &KrdantaArgs) -> Vec<Prakriya> {
+        let key = || CacheKey::Krdanta(dhatu.clone(), args.clone());
+        self.with_cache(key, || {
+            self.find_all(|p| derive_krdanta(p, dhatu, args), self.log_steps)
+        })
+    }
+
+    /// Returns the distinct tinantas that can be derived with the given initial conditions.
+    ///
+    /// Use this instead of `derive_tinantas` when you need only the final forms. We still run the
+    /// full derivation, but we don't save its history, so we avoid formatting a string for each
+    /// step. The forms are returned in sorted order.
+    ///
+    /// If the cache is enabled, we reuse a cached result from `derive_tinantas`. But we don't add
+    /// our own result to the cache, since its prakriyas have no history.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// # use vidyut_prakriya::Ashtadhyayi;
+    /// # use vidyut_prakriya::args::*;
+    /// let a = Ashtadhyayi::new();
+    /// let dhatu = Dhatu::new("BU", Gana::Bhvadi);
+    /// let args = TinantaArgs::builder()
+    ///     .lakara(Lakara::Lat)
+    ///     .prayoga(Prayoga::Kartari)
+    ///     .purusha(Purusha::Prathama)
+    ///     .vacana(Vacana::Eka)
+    ///     .build()?;
+    /// assert_eq!(a.derive_tinanta_forms(&dhatu, &args), vec!["Bavati"]);
+    /// # Ok::<(), ArgumentError>(())
+    /// ```
+    pub fn derive_tinanta_forms(&self, dhatu: &Dhatu, args: &TinantaArgs) -> Vec<String> {
+        let key = || CacheKey::Tinanta(dhatu.clone(), args.clone());
+        self.with_cached_forms(key, || {
+            let (prakriyas, _) = self.find_all(|p| derive_tinanta(p, dhatu, args), false);
+            filter_by_pada(prakriyas, args)
+        })
+    }
+
+    /// Returns the distinct subantas that can be derived with the given initial conditions.
+    ///
+    /// Use this instead of `derive_subantas` when you need only the final forms. We still run the
+    /// full derivation, but we don't save its history, so we avoid formatting a string for each
+    /// step. The forms are returned in sorted order.
+    ///
+    /// If the cache is enabled, we reuse a cached result from `derive_subantas`. But we don't add
+    /// our own result to the cache, since its prakriyas have no history.
+    pub fn derive_subanta_forms(&self, pratipadika: &str, args: &SubantaArgs) -> Vec<String> {
+        let key = || CacheKey::Subanta(CompactString::from(pratipadika), args.clone());
+        self.with_cached_forms(key, || {
+            self.find_all(|p| derive_subanta(p, pratipadika, args), false)
+                .0
+        })
+    }
+
+    /// Returns the distinct krdantas that can be derived with the given initial conditions.
+    ///
+    /// Use this instead of `derive_krdantas` when you need only the final forms. We still run the
+    /// full derivation, but we don't save its history, so we avoid formatting a string for each
+    /// step. The forms are returned in sorted order.
+    ///
+    /// If the cache is enabled, we reuse a cached result from `derive_krdantas`. But we don't add
+    /// our own result to the cache, since its prakriyas have no history.
+    pub fn derive_krdanta_forms(&self, dhatu: &Dhatu, args: &KrdantaArgs) -> Vec<String> {
+        let key = || CacheKey::Krdanta(dhatu.clone(), args.clone());
+        self.with_cached_forms(key, || {
+            self.find_all(|p| derive_krdanta(p, dhatu, args), false).0
+        })
+    }
+
+    /// Runs `derive` and returns all of the prakriyas it produces, along with whether we explored
+    /// every path. We stop exploring early if the step observer asks us to.
+    fn find_all(
+        &self,
+        derive: impl Fn(&mut Prakriya) -> Result<()>,
+        log_steps: bool,
+    ) -> (Vec<Prakriya>, bool) {
+        // `log_steps` runs first so that the step observer sees a history that includes the
+        // current step.
+        let mut observers = Vec::new();
+        if log_steps {
+            observers.push(Observer::LogSteps);
+        }
+        if let Some(f) = &self.step_observer {
+            observers.push(Observer::Callback(f.clone()));
+        }
+
         let mut stack = PrakriyaStack::new();
-        stack.find_all(|p| derive_krdanta(p, dhatu, args), self.log_steps);
-        stack.prakriyas()
+        // TODO: handle error properly.
+        stack.find_all(derive, &observers, &self.profiler);
+        self.num_failed_derivations
+            .fetch_add(stack.num_failed(), Ordering::Relaxed);
+        let is_complete = !stack.is_stopped();
+        (stack.prakriyas(), is_complete)
+    }
+
+    /// Runs `derive`, or returns its cached result if the cache is enabled.
+    ///
+    /// We create the key lazily so that we don't pay for it when the cache is disabled. We don't
+    /// hold the lock while deriving, so other threads can use the cache in the meantime. If the
+    /// step observer stopped the derivation early, the result is incomplete and we don't cache it.
+    fn with_cache(
+        &self,
+        key: impl FnOnce() -> CacheKey,
+        derive: impl FnOnce() -> (Vec<Prakriya>, bool),
+    ) -> Vec<Prakriya> {
+        match &self.cache {
+            Some(cache) => {
+                let key = key();
+                if let Some(prakriyas) = lock(cache).get(&key) {
+                    return prakriyas;
+                }
+                let (prakriyas, is_complete) = derive();
+                if is_complete {
+                    lock(cache).insert(key, &prakriyas);
+                }
+                prakriyas
+            }
+            None => derive().0,
+        }
+    }
+
+    /// Returns the forms of the cached result for `key` if one exists, or else the forms of the
+    /// prakriyas produced by `derive`.
+    ///
+    /// Unlike `with_cache`, we never insert into the cache here, since `derive` doesn't log steps.
+    fn with_cached_forms(
+        &self,
+        key: impl FnOnce() -> CacheKey,
+        derive: impl FnOnce() -> Vec<Prakriya>,
+    ) -> Vec<String> {
+        if let Some(cache) = &self.cache {
+            if let Some(prakriyas) = lock(cache).get(&key()) {
+                return to_forms(&prakriyas);
+            }
+        }
+        to_forms(&derive())
+    }
+
+    /// Returns the full tinanta paradigm for the given dhatu, prayoga, and lakara.
+    ///
+    /// The paradigm contains one entry per purusha and vacana, in the order that grammars
+    /// traditionally list them: prathama-eka, prathama-dvi, prathama-bahu, madhyama-eka, and so
+    /// on. Each entry pairs the arguments for that slot with the prakriyas derived for it.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// # use vidyut_prakriya::Ashtadhyayi;
+    /// # use vidyut_prakriya::args::*;
+    /// let a = Ashtadhyayi::new();
+    /// let dhatu = Dhatu::new("BU", Gana::Bhvadi);
+    /// let paradigm = a.derive_tinanta_paradigm(&dhatu, Prayoga::Kartari, Lakara::Lat);
+    /// assert_eq!(paradigm.len(), 9);
+    /// ```
+    pub fn derive_tinanta_paradigm(
+        &self,
+        dhatu: &Dhatu,
+        prayoga: Prayoga,
+        lakara: Lakara,
+    ) -> Vec<(TinantaArgs, Vec<Prakriya>)> {
+        let mut ret = Vec::new();
+        for purusha in PURUSHAS {
+            for vacana in VACANAS {
+                let args = TinantaArgs::builder()
+                    .prayoga(prayoga)
+                    .purusha(*purusha)
+                    .vacana(*vacana)
+                    .lakara(lakara)
+                    .build();
+                // Since we set all fields above, `build()` should never fail.
+                if let Ok(args) = args {
+                    let prakriyas = self.derive_tinantas(dhatu, &args);
+                    ret.push((args, prakriyas));
+                }
+            }
+        }
+        ret
+    }
+
+    /// Returns the full subanta paradigm for the given pratipadika and linga.
+    ///
+    /// The paradigm contains one entry per vibhakti and vacana, in the order that grammars
+    /// traditionally list them: prathama-eka, prathama-dvi, prathama-bahu, dvitiya-eka, and so on,
+    /// ending with sambodhana. Each entry pairs the arguments for that slot with the prakriyas
+    /// derived for it.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// # use vidyut_prakriya::Ashtadhyayi;
+    /// # use vidyut_prakriya::args::*;
+    /// let a = Ashtadhyayi::new();
+    /// let paradigm = a.derive_subanta_paradigm("nara", Linga::Pum);
+    /// assert_eq!(paradigm.len(), 24);
+    /// ```
+    pub fn derive_subanta_paradigm(
+        &self,
+        pratipadika: &str,
+        linga: Linga,
+    ) -> Vec<(SubantaArgs, Vec<Prakriya>)> {
+        let mut ret = Vec::new();
+        for vibhakti in VIBHAKTIS {
+            for vacana in VACANAS {
+                let args = SubantaArgs::builder()
+                    .linga(linga)
+                    .vibhakti(*vibhakti)
+                    .vacana(*vacana)
+                    .build();
+                // Since we set all fields above, `build()` should never fail.
+                if let Ok(args) = args {
+                    let prakriyas = self.derive_subantas(pratipadika, &args);
+                    ret.push((args, prakriyas));
+                }
+            }
+        }
+        ret
+    }
+
+    /// Returns a report of how often each rule was applied and how long it took.
+    ///
+    /// The report covers every derivation run since this `Ashtadhyayi` was created or since the
+    /// last call to `reset_profile`. If profiling is disabled, returns `None`.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// # use vidyut_prakriya::Ashtadhyayi;
+    /// # use vidyut_prakriya::args::*;
+    /// let a = Ashtadhyayi::builder().profile(true).build();
+    /// let dhatu = Dhatu::new("BU", Gana::Bhvadi);
+    /// let args = KrdantaArgs::builder().krt(Krt::ktvA).build()?;
+    /// a.derive_krdantas(&dhatu, &args);
+    ///
+    /// let report = a.profile_report().unwrap();
+    /// for (rule, stats) in report.by_time().iter().take(10) {
+    ///     println!("{rule}: {} times, {:?}", stats.count, stats.time);
+    /// }
+    /// # Ok::<(), ArgumentError>(())
+    /// ```
+    pub fn profile_report(&self) -> Option<ProfileReport> {
+        self.profiler.as_ref().map(|p| p.report())
+    }
+
+    /// Clears all profiling data collected so far.
+    pub fn reset_profile(&self) {
+        if let Some(p) = &self.profiler {
+            p.reset();
+        }
+    }
+
+    /// Returns the number of derivations that failed since this `Ashtadhyayi` was created.
+    ///
+    /// We run one derivation for each combination of optional rules that we explore, and a failed
+    /// derivation produces no prakriya. So a high count here is wasted work and suggests that
+    /// some optional rule offers an alternative that can never succeed. Unlike `profile_report`,
+    /// this count is always available. Cache hits run no derivations and so add nothing.
+    pub fn num_failed_derivations(&self) -> usize {
+        self.num_failed_derivations.load(Ordering::Relaxed)
     }
 }
 
@@ -307,11 +648,80 @@ impl AshtadhyayiBuilder {
     /// - If `false`, the program will run faster, but only the final output of the `Prakriya` will
     ///   be available. This is best used when you want to generate a word list and don't need the
     ///   underlying derivation.
+    ///
+    /// Internally, this is a built-in step observer that saves each step to `Prakriya::history`.
     pub fn log_steps(mut self, value: bool) -> Self {
         self.a.log_steps = value;
         self
     }
 
+    /// *(default: none)* Sets a callback that runs each time a rule is applied.
+    ///
+    /// The callback receives the prakriya just after the rule has been applied, along with the
+    /// rule itself. Use it to stream a derivation to a log or UI as it happens. Since we explore
+    /// every combination of optional rules, the callback will see the steps of every prakriya
+    /// that we try, including the ones that eventually fail.
+    ///
+    /// The callback returns whether to continue. If it returns `ControlFlow::Break`, we stop the
+    /// current derivation, discard it, and skip any combinations of optional rules that we have
+    /// yet to try. The prakriyas that finished before then are returned as usual, but we don't
+    /// cache them, since they might not be complete.
+    ///
+    /// `log_steps` is itself a built-in observer that runs just before this one, so if
+    /// `log_steps` is `true`, `p.history()` already includes the current step.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// # use vidyut_prakriya::Ashtadhyayi;
+    /// use std::ops::ControlFlow;
+    ///
+    /// let a = Ashtadhyayi::builder()
+    ///     .log_steps(false)
+    ///     .step_observer(|p, rule| {
+    ///         println!("{rule}: {}", p.text());
+    ///         ControlFlow::Continue(())
+    ///     })
+    ///     .build();
+    /// ```
+    pub fn step_observer(
+        mut self,
+        f: impl FnMut(&Prakriya, Rule) -> ControlFlow<()> + Send + 'static,
+    ) -> Self {
+        self.a.step_observer = Some(StepObserver::new(f));
+        self
+    }
+
+    /// *(default: false)* Controls whether or not to profile the rules applied in each prakriya.
+    ///
+    /// If `true`, we count how many times each rule is applied and how long it took. Use
+    /// `Ashtadhyayi::profile_report` to see the results. Profiling adds some overhead to each
+    /// step, so we recommend it only when investigating performance.
+    pub fn profile(mut self, value: bool) -> Self {
+        self.a.profiler = if value { Some(Profiler::new()) } else { None };
+        self
+    }
+
+    /// *(default: 0)* Sets the maximum number of derivations to cache.
+    ///
+    /// If greater than 0, we cache the prakriyas for each set of derivation arguments and return
+    /// copies of them when the same arguments are used again. Once the cache is full, we evict
+    /// the least recently used entry first. If 0, we disable the cache.
+    ///
+    /// The cache is safe to share across threads, so a server can use a single `Ashtadhyayi`
+    /// for all of its requests.
+    ///
+    /// A cache hit skips the derivation entirely, so the step observer and profiler (if any) see
+    /// only the steps of the first derivation.
+    pub fn cache_capacity(mut self, value: usize) -> Self {
+        self.a.cache = if value > 0 {
+            Some(Mutex::new(PrakriyaCache::new(value)))
+        } else {
+            None
+        };
+        self
+    }
+
     /// Creates an `Ashtadhyayi` object.
     pub fn build(self) -> Ashtadhyayi {
         self.a
@@ -0,0 +1,150 @@
+/// A bounded cache of derivation results.
+///
+/// Deriving a word runs hundreds of rules, so clients that derive the same words repeatedly (for
+/// example, a web service) can save a lot of work by caching results. The cache is off by
+/// default. To enable it, use `AshtadhyayiBuilder::cache_capacity`.
+use crate::args::{Dhatu, KrdantaArgs, SubantaArgs, TinantaArgs};
+use crate::prakriya::Prakriya;
+use compact_str::CompactString;
+use std::collections::{BTreeMap, HashMap};
+
+/// The arguments to a single derivation.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub(crate) enum CacheKey {
+    Tinanta(Dhatu, TinantaArgs),
+    Subanta(CompactString, SubantaArgs),
+    Krdanta(Dhatu, KrdantaArgs),
+}
+
+/// A cached result and when it was last used.
+#[derive(Debug)]
+struct Entry {
+    prakriyas: Vec<Prakriya>,
+    last_used: u64,
+}
+
+/// Maps derivation arguments to the prakriyas they produce.
+///
+/// Once the cache is full, we evict the least recently used entry first. Both `get` and `insert`
+/// count as a use.
+#[derive(Debug)]
+pub(crate) struct PrakriyaCache {
+    capacity: usize,
+    entries: HashMap<CacheKey, Entry>,
+    /// Maps the `last_used` time of each entry to its key, from least to most recently used.
+    by_last_used: BTreeMap<u64, CacheKey>,
+    /// A counter that increases with each use of the cache.
+    clock: u64,
+}
+
+impl PrakriyaCache {
+    /// Creates an empty cache that holds at most `capacity` entries.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            by_last_used: BTreeMap::new(),
+            clock: 0,
+        }
+    }
+
+    /// Returns a copy of the prakriyas cached for `key`, if any, and marks `key` as recently used.
+    pub fn get(&mut self, key: &CacheKey) -> Option<Vec<Prakriya>> {
+        let now = self.tick();
+        let entry = self.entries.get_mut(key)?;
+        self.by_last_used.remove(&entry.last_used);
+        self.by_last_used.insert(now, key.clone());
+        entry.last_used = now;
+        Some(entry.prakriyas.clone())
+    }
+
+    /// Caches the prakriyas for `key`, evicting the least recently used entry if the cache is
+    /// full.
+    pub fn insert(&mut self, key: CacheKey, prakriyas: &[Prakriya]) {
+        if self.capacity == 0 || self.entries.contains_key(&key) {
+            return;
+        }
+        if self.entries.len() >= self.capacity {
+            if let Some((_, oldest)) = self.by_last_used.pop_first() {
+                self.entries.remove(&oldest);
+            }
+        }
+        let now = self.tick();
+        self.by_last_used.insert(now, key.clone());
+        self.entries.insert(
+            key,
+            Entry {
+                prakriyas: prakriyas.to_vec(),
+                last_used: now,
+            },
+        );
+    }
+
+    /// Advances the clock and returns the new time.
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::{Linga, Vacana, Vibhakti};
+
+    fn key(pratipadika: &str) -> CacheKey {
+        let args = SubantaArgs::builder()
+            .linga(Linga::Pum)
+            .vibhakti(Vibhakti::Prathama)
+            .vacana(Vacana::Eka)
+            .build()
+            .unwrap();
+        CacheKey::Subanta(CompactString::from(pratipadika), args)
+    }
+
+    #[test]
+    fn test_insert_and_get() {
+        let mut cache = PrakriyaCache::new(2);
+        assert!(cache.get(&key("nara")).is_none());
+
+        cache.insert(key("nara"), &[Prakriya::new()]);
+        assert_eq!(cache.get(&key("nara")).unwrap().len(), 1);
+        assert!(cache.get(&key("deva")).is_none());
+    }
+
+    #[test]
+    fn test_evicts_oldest_entry() {
+        let mut cache = PrakriyaCache::new(2);
+        cache.insert(key("nara"), &[]);
+        cache.insert(key("deva"), &[]);
+        cache.insert(key("rAma"), &[]);
+
+        assert_eq!(cache.entries.len(), 2);
+        assert!(cache.get(&key("nara")).is_none());
+        assert!(cache.get(&key("deva")).is_some());
+        assert!(cache.get(&key("rAma")).is_some());
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used_entry() {
+        let mut cache = PrakriyaCache::new(2);
+        cache.insert(key("nara"), &[]);
+        cache.insert(key("deva"), &[]);
+        // `nara` is older, but using it makes `deva` the least recently used.
+        assert!(cache.get(&key("nara")).is_some());
+        cache.insert(key("rAma"), &[]);
+
+        assert_eq!(cache.entries.len(), 2);
+        assert_eq!(cache.by_last_used.len(), 2);
+        assert!(cache.get(&key("deva")).is_none());
+        assert!(cache.get(&key("nara")).is_some());
+        assert!(cache.get(&key("rAma")).is_some());
+    }
+
+    #[test]
+    fn test_zero_capacity() {
+        let mut cache = PrakriyaCache::new(0);
+        cache.insert(key("nara"), &[]);
+        assert_eq!(cache.entries.len(), 0);
+    }
+}
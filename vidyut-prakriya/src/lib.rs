@@ -3,6 +3,7 @@
 
 pub use crate::ashtadhyayi::{Ashtadhyayi, AshtadhyayiBuilder};
 pub use crate::prakriya::{Prakriya, Rule, RuleChoice, Step};
+pub use crate::profile::{ProfileReport, RuleStats};
 
 // Public modules.
 // - `args` defines the API contract.
@@ -20,9 +21,11 @@ pub mod private {
 }
 
 // Data structures
+mod cache;
 mod char_view;
 mod prakriya;
 mod prakriya_stack;
+mod profile;
 mod sounds;
 mod tag;
 mod term;
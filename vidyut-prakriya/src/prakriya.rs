@@ -1,7 +1,12 @@
+use crate::profile::Profiler;
 use crate::tag::Tag;
 use crate::term::{Term, TermView};
 use compact_str::CompactString;
 use enumset::EnumSet;
+use std::fmt;
+use std::mem;
+use std::ops::ControlFlow;
+use std::sync::{Arc, Mutex, PoisonError};
 
 /// A simple string label for some rule in the grammar.
 ///
@@ -19,7 +24,7 @@ use enumset::EnumSet;
 pub type Rule = &'static str;
 
 /// Represents a step of the derivation.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Step {
     rule: Rule,
     result: String,
@@ -46,11 +51,80 @@ pub enum RuleChoice {
     Decline(Rule),
 }
 
+impl RuleChoice {
+    /// The rule that was accepted or declined.
+    pub fn rule(&self) -> Rule {
+        match self {
+            RuleChoice::Accept(rule) | RuleChoice::Decline(rule) => rule,
+        }
+    }
+
+    /// The Ashtadhyayi sutra that this choice's rule belongs to, in the traditional
+    /// `<adhyaya>.<pada>.<rule>` format.
+    ///
+    /// For a rule from a commentary, such as `"1.2.6.v1"`, or for a variant of a rule, such as
+    /// `"3.1.40:kf"`, this returns the sutra that the rule is attached to (here, `"1.2.6"` and
+    /// `"3.1.40"`). For an ad-hoc label that is not tied to a specific sutra, such as
+    /// `"AkusmIya"`, this returns `None`.
+    pub fn sutra(&self) -> Option<&'static str> {
+        let rule = self.rule();
+        let base = rule.split(':').next().unwrap_or(rule);
+        let parts: Vec<&str> = base.splitn(4, '.').collect();
+        let is_number = |s: &&str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+        if parts.len() >= 3 && parts[..3].iter().all(is_number) {
+            let len = parts[0].len() + parts[1].len() + parts[2].len() + 2;
+            Some(&rule[..len])
+        } else {
+            None
+        }
+    }
+}
+
+/// A function that observes a single derivation step and decides whether to continue.
+type StepFn = dyn FnMut(&Prakriya, Rule) -> ControlFlow<()> + Send;
+
+/// A callback that runs each time a rule is applied during a derivation.
+///
+/// We wrap the callback in an `Arc<Mutex<..>>` so that all of the prakriyas created for a single
+/// input can share the same observer, and so that `Ashtadhyayi` and `Prakriya` stay `Send` and
+/// `Sync`.
+#[derive(Clone)]
+pub(crate) struct StepObserver(Arc<Mutex<StepFn>>);
+
+impl StepObserver {
+    pub fn new(f: impl FnMut(&Prakriya, Rule) -> ControlFlow<()> + Send + 'static) -> Self {
+        Self(Arc::new(Mutex::new(f)))
+    }
+
+    fn call(&self, p: &Prakriya, rule: Rule) -> ControlFlow<()> {
+        // If the callback panicked on another thread, its state is the user's concern, so we
+        // ignore lock poisoning.
+        let mut f = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        f(p, rule)
+    }
+}
+
+impl fmt::Debug for StepObserver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "StepObserver")
+    }
+}
+
+/// Something that runs each time a rule is applied during a derivation.
+#[derive(Clone, Debug)]
+pub(crate) enum Observer {
+    /// Saves each step to `Prakriya::history`. This is how we implement `log_steps`.
+    LogSteps,
+    /// Runs a callback provided by the user.
+    Callback(StepObserver),
+}
+
 /// Configuration options that affect how a `Prakriya` behaves during the derivation.
-#[derive(Default, Debug)]
+#[derive(Clone, Default, Debug)]
 pub struct Config {
     pub rule_choices: Vec<RuleChoice>,
-    pub log_steps: bool,
+    pub observers: Vec<Observer>,
+    pub profiler: Option<Profiler>,
 }
 
 impl Config {
@@ -60,13 +134,14 @@ impl Config {
 }
 
 /// Models a Paninian derivation.
-#[derive(Default, Debug)]
+#[derive(Clone, Default, Debug)]
 pub struct Prakriya {
     terms: Vec<Term>,
     tags: EnumSet<Tag>,
     history: Vec<Step>,
     config: Config,
     rule_decisions: Vec<RuleChoice>,
+    is_stopped: bool,
 }
 
 /// Public API
@@ -105,6 +180,7 @@ impl Prakriya {
             history: Vec::new(),
             config: Config::new(),
             rule_decisions: Vec::new(),
+            is_stopped: false,
         }
     }
 
@@ -314,21 +390,55 @@ impl Prakriya {
         }
     }
 
-    /// Adds a rule to the history.
+    /// Notifies our observers that `rule` was just applied.
+    ///
+    /// If an observer returns `ControlFlow::Break`, we mark this prakriya as stopped and ignore
+    /// all later steps. The caller should then abandon the derivation.
     pub(crate) fn step(&mut self, rule: Rule) {
-        if self.config.log_steps {
-            let state = self.terms.iter().fold(String::new(), |a, b| {
-                if a.is_empty() {
-                    a + &b.text
-                } else {
-                    a + " + " + &b.text
+        if self.is_stopped {
+            return;
+        }
+
+        // Take the observers out of `self` so that we can pass `self` to them.
+        let observers = mem::take(&mut self.config.observers);
+        for observer in &observers {
+            let flow = match observer {
+                Observer::LogSteps => {
+                    self.log_step(rule);
+                    ControlFlow::Continue(())
                 }
-            });
-            self.history.push(Step {
-                rule,
-                result: state,
-            })
+                Observer::Callback(f) => f.call(self, rule),
+            };
+            if flow.is_break() {
+                self.is_stopped = true;
+                break;
+            }
         }
+        self.config.observers = observers;
+
+        if let Some(profiler) = &mut self.config.profiler {
+            profiler.record(rule);
+        }
+    }
+
+    /// Adds a rule and the current state of the derivation to the history.
+    fn log_step(&mut self, rule: Rule) {
+        let state = self.terms.iter().fold(String::new(), |a, b| {
+            if a.is_empty() {
+                a + &b.text
+            } else {
+                a + " + " + &b.text
+            }
+        });
+        self.history.push(Step {
+            rule,
+            result: state,
+        })
+    }
+
+    /// Returns whether an observer has stopped this derivation.
+    pub(crate) fn is_stopped(&self) -> bool {
+        self.is_stopped
     }
 
     /// (debug) Writes the given string to the history.
@@ -382,3 +492,30 @@ impl Prakriya {
         self.rule_decisions.push(RuleChoice::Decline(rule));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rule_choice_rule() {
+        assert_eq!(RuleChoice::Accept("6.1.77").rule(), "6.1.77");
+        assert_eq!(RuleChoice::Decline("6.1.77").rule(), "6.1.77");
+    }
+
+    #[test]
+    fn test_rule_choice_sutra() {
+        let cases = [
+            ("6.1.77", Some("6.1.77")),
+            ("3.2.174", Some("3.2.174")),
+            ("1.2.6.v1", Some("1.2.6")),
+            ("3.1.40:kf", Some("3.1.40")),
+            ("AkusmIya", None),
+            ("1.2", None),
+        ];
+        for (rule, expected) in cases {
+            assert_eq!(RuleChoice::Accept(rule).sutra(), expected);
+            assert_eq!(RuleChoice::Decline(rule).sutra(), expected);
+        }
+    }
+}
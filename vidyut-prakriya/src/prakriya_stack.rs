@@ -1,4 +1,5 @@
-use crate::prakriya::{Config, Prakriya, RuleChoice};
+use crate::prakriya::{Config, Observer, Prakriya, RuleChoice};
+use crate::profile::Profiler;
 use std::error::Error;
 
 /// Explores all optional derivations for some input.
@@ -12,6 +13,10 @@ pub(crate) struct PrakriyaStack {
     prakriyas: Vec<Prakriya>,
     /// Combinations of optional rules that we have yet to try.
     paths: Vec<Vec<RuleChoice>>,
+    /// Whether an observer stopped the search before we tried every path.
+    is_stopped: bool,
+    /// The number of derivations that failed and thus produced no prakriya.
+    num_failed: usize,
 }
 
 impl PrakriyaStack {
@@ -21,32 +26,63 @@ impl PrakriyaStack {
     }
 
     /// Creates a new `Prakriya` according to upstream options.
-    fn new_prakriya(rule_choices: Vec<RuleChoice>, log_steps: bool) -> Prakriya {
+    fn new_prakriya(
+        rule_choices: Vec<RuleChoice>,
+        observers: &[Observer],
+        profiler: &Option<Profiler>,
+    ) -> Prakriya {
         Prakriya::with_config(Config {
             rule_choices,
-            log_steps,
+            observers: observers.to_vec(),
+            profiler: profiler.as_ref().map(Profiler::start),
         })
     }
 
     /// Finds all variants of the given derivation function.
     ///
-    /// `derive` should accept an empty `Prakriya` and mutate it in-place.
+    /// `derive` should accept an empty `Prakriya` and mutate it in-place. If an observer stops a
+    /// derivation, we discard it and stop the search.
     pub fn find_all(
         &mut self,
         derive: impl Fn(&mut Prakriya) -> Result<(), Box<dyn Error>>,
-        log_steps: bool,
+        observers: &[Observer],
+        profiler: &Option<Profiler>,
     ) {
-        let mut p_init = Self::new_prakriya(vec![], log_steps);
-        // TODO: handle errors better.
-        if derive(&mut p_init).is_ok() {
-            self.add_prakriya(p_init, &[]);
-        }
-
-        while let Some(path) = self.pop_path() {
-            let mut p = Self::new_prakriya(path.clone(), log_steps);
-            if derive(&mut p).is_ok() {
+        let mut path = Vec::new();
+        loop {
+            let mut p = Self::new_prakriya(path.clone(), observers, profiler);
+            // TODO: handle errors better.
+            let is_ok = derive(&mut p).is_ok();
+            if p.is_stopped() {
+                self.paths.clear();
+                self.is_stopped = true;
+                return;
+            }
+            self.finish(profiler, is_ok);
+            if is_ok {
                 self.add_prakriya(p, &path);
             }
+
+            match self.pop_path() {
+                Some(next) => path = next,
+                None => return,
+            }
+        }
+    }
+
+    /// Counts the result of a single derivation and reports it to the profiler, if any.
+    ///
+    /// We don't try to prune paths that will fail. Derivations are deterministic, and we queue
+    /// new paths only from a prakriya that succeeded, so a failed path has no alternatives in the
+    /// queue and is never tried twice. And we can't know in advance whether a path will fail,
+    /// since a rule that fails in one context might succeed in another. So instead, we count
+    /// failures so that we can see how much work they waste.
+    fn finish(&mut self, profiler: &Option<Profiler>, is_ok: bool) {
+        if !is_ok {
+            self.num_failed += 1;
+        }
+        if let Some(profiler) = profiler {
+            profiler.finish(is_ok);
         }
     }
 
@@ -95,6 +131,16 @@ impl PrakriyaStack {
         self.paths.pop()
     }
 
+    /// Returns whether an observer stopped the search before we tried every path.
+    pub fn is_stopped(&self) -> bool {
+        self.is_stopped
+    }
+
+    /// Returns the number of derivations that failed and thus produced no prakriya.
+    pub fn num_failed(&self) -> usize {
+        self.num_failed
+    }
+
     /// Returns all of the prakriyas this stack has found. This consumes the stack.
     pub fn prakriyas(self) -> Vec<Prakriya> {
         self.prakriyas
@@ -0,0 +1,177 @@
+/// Rule-level profiling for derivations.
+///
+/// Profiling is off by default. To enable it, use `AshtadhyayiBuilder::profile` and read the
+/// results with `Ashtadhyayi::profile_report`.
+use crate::prakriya::Rule;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Aggregate statistics for a single rule.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct RuleStats {
+    /// The number of times this rule was applied.
+    pub count: usize,
+    /// The cumulative time spent on this rule.
+    ///
+    /// We measure this as the time since the previous step (or since the derivation started), so
+    /// it also includes the time spent checking any rules that did not apply in between.
+    pub time: Duration,
+}
+
+/// A summary of which rules were applied and how long they took.
+#[derive(Clone, Debug, Default)]
+pub struct ProfileReport {
+    rules: HashMap<Rule, RuleStats>,
+    num_derivations: usize,
+    num_failed_derivations: usize,
+}
+
+impl ProfileReport {
+    /// Returns the stats for the given rule, if the rule was ever applied.
+    pub fn get(&self, rule: Rule) -> Option<&RuleStats> {
+        self.rules.get(rule)
+    }
+
+    /// Returns all rules and their stats, sorted by cumulative time in descending order.
+    pub fn by_time(&self) -> Vec<(Rule, RuleStats)> {
+        let mut ret: Vec<_> = self.rules.iter().map(|(r, s)| (*r, *s)).collect();
+        ret.sort_by(|x, y| y.1.time.cmp(&x.1.time).then(x.0.cmp(y.0)));
+        ret
+    }
+
+    /// Returns all rules and their stats, sorted by count in descending order.
+    pub fn by_count(&self) -> Vec<(Rule, RuleStats)> {
+        let mut ret: Vec<_> = self.rules.iter().map(|(r, s)| (*r, *s)).collect();
+        ret.sort_by(|x, y| y.1.count.cmp(&x.1.count).then(x.0.cmp(y.0)));
+        ret
+    }
+
+    /// Returns the total number of steps across all rules.
+    pub fn total_count(&self) -> usize {
+        self.rules.values().map(|s| s.count).sum()
+    }
+
+    /// Returns the total time across all rules.
+    pub fn total_time(&self) -> Duration {
+        self.rules.values().map(|s| s.time).sum()
+    }
+
+    /// Returns the number of derivations we ran, including the ones that failed.
+    ///
+    /// We run one derivation for each combination of optional rules that we explore.
+    pub fn num_derivations(&self) -> usize {
+        self.num_derivations
+    }
+
+    /// Returns the number of derivations that failed and thus produced no prakriya.
+    ///
+    /// A failed derivation is wasted work, so a high count here suggests that some optional rule
+    /// is offering an alternative that can never succeed.
+    pub fn num_failed_derivations(&self) -> usize {
+        self.num_failed_derivations
+    }
+}
+
+/// Collects a `ProfileReport` as rules are applied.
+///
+/// All of the prakriyas created by an `Ashtadhyayi` share the same report. We wrap the report in
+/// an `Arc<Mutex<..>>` so that an `Ashtadhyayi` with profiling enabled is still `Send` and `Sync`.
+/// Each prakriya has its own copy of `last`, so derivations that run concurrently on different
+/// threads don't disturb each other's timings.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Profiler {
+    report: Arc<Mutex<ProfileReport>>,
+    /// When the current derivation last applied a rule, or when it started.
+    last: Option<Instant>,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a profiler for a new derivation that adds to the same report as this one.
+    pub fn start(&self) -> Self {
+        Self {
+            report: Arc::clone(&self.report),
+            last: Some(Instant::now()),
+        }
+    }
+
+    /// Records that `rule` was just applied.
+    pub fn record(&mut self, rule: Rule) {
+        let now = Instant::now();
+        let elapsed = self.last.map_or(Duration::ZERO, |t| now - t);
+        self.last = Some(now);
+
+        let mut report = self.lock();
+        let stats = report.rules.entry(rule).or_default();
+        stats.count += 1;
+        stats.time += elapsed;
+    }
+
+    /// Records that a derivation finished, either successfully or not.
+    pub fn finish(&self, is_ok: bool) {
+        let mut report = self.lock();
+        report.num_derivations += 1;
+        if !is_ok {
+            report.num_failed_derivations += 1;
+        }
+    }
+
+    /// Returns a copy of the report so far.
+    pub fn report(&self) -> ProfileReport {
+        self.lock().clone()
+    }
+
+    /// Clears all data collected so far.
+    pub fn reset(&self) {
+        *self.lock() = ProfileReport::default();
+    }
+
+    /// Locks the shared report.
+    ///
+    /// A panic while the lock is held leaves the report in a consistent state, so we ignore lock
+    /// poisoning.
+    fn lock(&self) -> MutexGuard<'_, ProfileReport> {
+        self.report.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn test_profiler_is_send_and_sync() {
+        assert_send_sync::<Profiler>();
+        assert_send_sync::<ProfileReport>();
+    }
+
+    #[test]
+    fn test_profiler_shares_report_across_threads() {
+        let profiler = Profiler::new();
+        thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    let mut p = profiler.start();
+                    p.record("1.1.1");
+                    p.record("1.1.2");
+                    p.finish(true);
+                });
+            }
+        });
+
+        let report = profiler.report();
+        assert_eq!(report.get("1.1.1").unwrap().count, 4);
+        assert_eq!(report.total_count(), 8);
+        assert_eq!(report.num_derivations(), 4);
+
+        profiler.reset();
+        assert_eq!(profiler.report().total_count(), 0);
+    }
+}
@@ -0,0 +1,68 @@
+use std::str::FromStr;
+use vidyut_prakriya::args::*;
+use vidyut_prakriya::Ashtadhyayi;
+
+fn derive(dhatu: &Dhatu, args: &TinantaArgs) -> Vec<String> {
+    Ashtadhyayi::new().derive_tinanta_forms(dhatu, args)
+}
+
+#[test]
+fn test_build_tinanta_args_with_typed_enums() {
+    // let-lakAra, Atmanepada, prathama-purusha, dvivacana.
+    let args = TinantaArgs::builder()
+        .lakara(Lakara::Let)
+        .prayoga(Prayoga::Kartari)
+        .purusha(Purusha::Prathama)
+        .vacana(Vacana::Dvi)
+        .pada(Pada::Atmane)
+        .build()
+        .unwrap();
+    assert_eq!(args.lakara(), Lakara::Let);
+    assert_eq!(args.purusha(), Purusha::Prathama);
+    assert_eq!(args.vacana(), Vacana::Dvi);
+    assert_eq!(args.pada(), Some(Pada::Atmane));
+}
+
+#[test]
+fn test_pada_is_optional() {
+    let args = TinantaArgs::builder()
+        .lakara(Lakara::Lat)
+        .prayoga(Prayoga::Kartari)
+        .purusha(Purusha::Prathama)
+        .vacana(Vacana::Eka)
+        .build()
+        .unwrap();
+    assert_eq!(args.pada(), None);
+}
+
+#[test]
+fn test_pada_from_str() {
+    assert_eq!(Pada::from_str("parasmai").unwrap(), Pada::Parasmai);
+    assert_eq!(Pada::from_str("atmane").unwrap(), Pada::Atmane);
+    assert!(Pada::from_str("ubhaya").is_err());
+    assert_eq!(Pada::Atmane.as_str(), "atmane");
+}
+
+#[test]
+fn test_derive_with_pada() {
+    let kr = Dhatu::new("qukf\\Y", Gana::Tanadi);
+    let builder = || {
+        TinantaArgs::builder()
+            .lakara(Lakara::Lat)
+            .prayoga(Prayoga::Kartari)
+            .purusha(Purusha::Prathama)
+            .vacana(Vacana::Dvi)
+    };
+
+    let any = builder().build().unwrap();
+    let parasmai = builder().pada(Pada::Parasmai).build().unwrap();
+    let atmane = builder().pada(Pada::Atmane).build().unwrap();
+
+    assert_eq!(derive(&kr, &any), vec!["kurutaH", "kurvAte"]);
+    assert_eq!(derive(&kr, &parasmai), vec!["kurutaH"]);
+    assert_eq!(derive(&kr, &atmane), vec!["kurvAte"]);
+
+    // A parasmaipada-only dhatu has no Atmanepada forms.
+    let bhu = Dhatu::new("BU", Gana::Bhvadi);
+    assert!(derive(&bhu, &atmane).is_empty());
+}
@@ -0,0 +1,122 @@
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use vidyut_prakriya::args::*;
+use vidyut_prakriya::{Ashtadhyayi, Prakriya};
+
+fn bhavati_args() -> (Dhatu, TinantaArgs) {
+    let dhatu = Dhatu::new("BU", Gana::Bhvadi);
+    let args = TinantaArgs::builder()
+        .lakara(Lakara::Lat)
+        .prayoga(Prayoga::Kartari)
+        .purusha(Purusha::Prathama)
+        .vacana(Vacana::Eka)
+        .build()
+        .unwrap();
+    (dhatu, args)
+}
+
+fn assert_same(xs: &[Prakriya], ys: &[Prakriya]) {
+    assert_eq!(xs.len(), ys.len());
+    for (x, y) in xs.iter().zip(ys) {
+        assert_eq!(x.text(), y.text());
+        let x_steps: Vec<_> = x.history().iter().map(|s| (s.rule(), s.result())).collect();
+        let y_steps: Vec<_> = y.history().iter().map(|s| (s.rule(), s.result())).collect();
+        assert_eq!(x_steps, y_steps);
+        let x_choices: Vec<_> = x.rule_choices().iter().map(|c| c.rule()).collect();
+        let y_choices: Vec<_> = y.rule_choices().iter().map(|c| c.rule()).collect();
+        assert_eq!(x_choices, y_choices);
+    }
+}
+
+#[test]
+fn test_cache_hit_matches_fresh_derivation() {
+    let (dhatu, args) = bhavati_args();
+    let fresh = Ashtadhyayi::new().derive_tinantas(&dhatu, &args);
+
+    let num_steps = Arc::new(AtomicUsize::new(0));
+    let num_steps_clone = num_steps.clone();
+    let a = Ashtadhyayi::builder()
+        .cache_capacity(10)
+        .step_observer(move |_, _| {
+            num_steps_clone.fetch_add(1, Ordering::Relaxed);
+            ControlFlow::Continue(())
+        })
+        .build();
+
+    let first = a.derive_tinantas(&dhatu, &args);
+    let steps_after_first = num_steps.load(Ordering::Relaxed);
+    let second = a.derive_tinantas(&dhatu, &args);
+
+    // The second call should be a cache hit, so no rules should run.
+    assert_eq!(num_steps.load(Ordering::Relaxed), steps_after_first);
+    assert_same(&fresh, &first);
+    assert_same(&fresh, &second);
+}
+
+#[test]
+fn test_cache_distinguishes_args() {
+    let a = Ashtadhyayi::builder().cache_capacity(10).build();
+    let args = |vibhakti| {
+        SubantaArgs::builder()
+            .linga(Linga::Pum)
+            .vibhakti(vibhakti)
+            .vacana(Vacana::Eka)
+            .build()
+            .unwrap()
+    };
+
+    let v1 = a.derive_subantas("nara", &args(Vibhakti::Prathama));
+    let v3 = a.derive_subantas("nara", &args(Vibhakti::Trtiya));
+    let v1_again = a.derive_subantas("nara", &args(Vibhakti::Prathama));
+    assert_ne!(v1[0].text(), v3[0].text());
+    assert_same(&v1, &v1_again);
+}
+
+#[test]
+fn test_cache_skips_stopped_derivation() {
+    let (dhatu, args) = bhavati_args();
+    let should_stop = Arc::new(AtomicBool::new(true));
+    let should_stop_clone = should_stop.clone();
+    let a = Ashtadhyayi::builder()
+        .cache_capacity(10)
+        .step_observer(move |_, _| {
+            if should_stop_clone.load(Ordering::Relaxed) {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        })
+        .build();
+
+    assert!(a.derive_tinantas(&dhatu, &args).is_empty());
+
+    // The stopped result was incomplete, so we should derive again instead of using the cache.
+    should_stop.store(false, Ordering::Relaxed);
+    let fresh = Ashtadhyayi::new().derive_tinantas(&dhatu, &args);
+    assert_same(&fresh, &a.derive_tinantas(&dhatu, &args));
+}
+
+#[test]
+fn test_forms_use_cache_without_filling_it() {
+    let (dhatu, args) = bhavati_args();
+    let num_steps = Arc::new(AtomicUsize::new(0));
+    let num_steps_clone = num_steps.clone();
+    let a = Ashtadhyayi::builder()
+        .cache_capacity(10)
+        .step_observer(move |_, _| {
+            num_steps_clone.fetch_add(1, Ordering::Relaxed);
+            ControlFlow::Continue(())
+        })
+        .build();
+
+    // A cache miss derives the forms but doesn't add history-less prakriyas to the cache.
+    assert_eq!(a.derive_tinanta_forms(&dhatu, &args), vec!["Bavati"]);
+    let prakriyas = a.derive_tinantas(&dhatu, &args);
+    assert!(prakriyas.iter().all(|p| !p.history().is_empty()));
+
+    // Once `derive_tinantas` has filled the cache, the forms come from the cache.
+    let steps_before = num_steps.load(Ordering::Relaxed);
+    assert_eq!(a.derive_tinanta_forms(&dhatu, &args), vec!["Bavati"]);
+    assert_eq!(num_steps.load(Ordering::Relaxed), steps_before);
+}
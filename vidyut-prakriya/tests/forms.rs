@@ -0,0 +1,41 @@
+use vidyut_prakriya::args::*;
+use vidyut_prakriya::{Ashtadhyayi, Prakriya};
+
+fn to_forms(prakriyas: &[Prakriya]) -> Vec<String> {
+    let mut forms: Vec<_> = prakriyas.iter().map(|p| p.text().to_string()).collect();
+    forms.sort();
+    forms.dedup();
+    forms
+}
+
+#[test]
+fn test_tinanta_forms_match_prakriyas() {
+    let a = Ashtadhyayi::new();
+    for dhatu in [
+        Dhatu::new("BU", Gana::Bhvadi),
+        Dhatu::new("qukf\\Y", Gana::Tanadi),
+    ] {
+        for (args, prakriyas) in a.derive_tinanta_paradigm(&dhatu, Prayoga::Kartari, Lakara::Lit) {
+            assert_eq!(a.derive_tinanta_forms(&dhatu, &args), to_forms(&prakriyas));
+        }
+    }
+}
+
+#[test]
+fn test_subanta_forms_match_prakriyas() {
+    let a = Ashtadhyayi::new();
+    for (args, prakriyas) in a.derive_subanta_paradigm("nara", Linga::Pum) {
+        assert_eq!(a.derive_subanta_forms("nara", &args), to_forms(&prakriyas));
+    }
+}
+
+#[test]
+fn test_krdanta_forms_match_prakriyas() {
+    let a = Ashtadhyayi::new();
+    let dhatu = Dhatu::new("BU", Gana::Bhvadi);
+    for krt in [Krt::ktvA, Krt::tavya, Krt::Rvul] {
+        let args = KrdantaArgs::builder().krt(krt).build().unwrap();
+        let prakriyas = a.derive_krdantas(&dhatu, &args);
+        assert_eq!(a.derive_krdanta_forms(&dhatu, &args), to_forms(&prakriyas));
+    }
+}
@@ -0,0 +1,45 @@
+use vidyut_prakriya::args::*;
+use vidyut_prakriya::Ashtadhyayi;
+
+#[test]
+fn test_tinanta_paradigm() {
+    let a = Ashtadhyayi::new();
+    let dhatu = Dhatu::new("BU", Gana::Bhvadi);
+    let paradigm = a.derive_tinanta_paradigm(&dhatu, Prayoga::Kartari, Lakara::Lat);
+
+    let expected = &[
+        (Purusha::Prathama, Vacana::Eka, "Bavati"),
+        (Purusha::Prathama, Vacana::Dvi, "BavataH"),
+        (Purusha::Prathama, Vacana::Bahu, "Bavanti"),
+        (Purusha::Madhyama, Vacana::Eka, "Bavasi"),
+        (Purusha::Madhyama, Vacana::Dvi, "BavaTaH"),
+        (Purusha::Madhyama, Vacana::Bahu, "BavaTa"),
+        (Purusha::Uttama, Vacana::Eka, "BavAmi"),
+        (Purusha::Uttama, Vacana::Dvi, "BavAvaH"),
+        (Purusha::Uttama, Vacana::Bahu, "BavAmaH"),
+    ];
+    assert_eq!(paradigm.len(), expected.len());
+    for ((args, prakriyas), (purusha, vacana, text)) in paradigm.iter().zip(expected) {
+        assert_eq!(args.purusha(), *purusha);
+        assert_eq!(args.vacana(), *vacana);
+        assert_eq!(args.lakara(), Lakara::Lat);
+        let texts: Vec<_> = prakriyas.iter().map(|p| p.text()).collect();
+        assert_eq!(texts, vec![*text]);
+    }
+}
+
+#[test]
+fn test_subanta_paradigm() {
+    let a = Ashtadhyayi::new();
+    let paradigm = a.derive_subanta_paradigm("nara", Linga::Pum);
+    assert_eq!(paradigm.len(), 24);
+
+    let (args, prakriyas) = &paradigm[0];
+    assert_eq!(args.vibhakti(), Vibhakti::Prathama);
+    assert_eq!(args.vacana(), Vacana::Eka);
+    assert!(prakriyas.iter().any(|p| p.text() == "naraH"));
+
+    let (args, _) = &paradigm[23];
+    assert_eq!(args.vibhakti(), Vibhakti::Sambodhana);
+    assert_eq!(args.vacana(), Vacana::Bahu);
+}
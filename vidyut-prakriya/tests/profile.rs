@@ -0,0 +1,102 @@
+use vidyut_prakriya::args::*;
+use vidyut_prakriya::Ashtadhyayi;
+
+fn derive_bhavati(a: &Ashtadhyayi) -> usize {
+    let dhatu = Dhatu::new("BU", Gana::Bhvadi);
+    let args = TinantaArgs::builder()
+        .lakara(Lakara::Lat)
+        .prayoga(Prayoga::Kartari)
+        .purusha(Purusha::Prathama)
+        .vacana(Vacana::Eka)
+        .build()
+        .unwrap();
+    let prakriyas = a.derive_tinantas(&dhatu, &args);
+    prakriyas.iter().map(|p| p.history().len()).sum()
+}
+
+#[test]
+fn test_profile_disabled_by_default() {
+    let a = Ashtadhyayi::new();
+    derive_bhavati(&a);
+    assert!(a.profile_report().is_none());
+}
+
+#[test]
+fn test_profile_counts_rules() {
+    let a = Ashtadhyayi::builder().profile(true).build();
+    let num_logged = derive_bhavati(&a);
+
+    let report = a.profile_report().unwrap();
+    assert!(report.total_count() >= num_logged);
+    assert!(report.get("3.1.68").is_some());
+    assert!(report.get("not-a-rule").is_none());
+
+    let by_count = report.by_count();
+    assert!(by_count.windows(2).all(|w| w[0].1.count >= w[1].1.count));
+    let by_time = report.by_time();
+    assert!(by_time.windows(2).all(|w| w[0].1.time >= w[1].1.time));
+    assert_eq!(by_count.len(), by_time.len());
+}
+
+#[test]
+fn test_profile_accumulates_and_resets() {
+    let a = Ashtadhyayi::builder().profile(true).build();
+    derive_bhavati(&a);
+    let first = a.profile_report().unwrap().total_count();
+    derive_bhavati(&a);
+    assert_eq!(a.profile_report().unwrap().total_count(), 2 * first);
+
+    a.reset_profile();
+    assert_eq!(a.profile_report().unwrap().total_count(), 0);
+}
+
+#[test]
+fn test_profile_counts_derivations() {
+    let a = Ashtadhyayi::builder().profile(true).build();
+    let dhatu = Dhatu::new("qukf\\Y", Gana::Tanadi);
+    let args = TinantaArgs::builder()
+        .lakara(Lakara::Lat)
+        .prayoga(Prayoga::Kartari)
+        .purusha(Purusha::Prathama)
+        .vacana(Vacana::Eka)
+        .build()
+        .unwrap();
+    let prakriyas = a.derive_tinantas(&dhatu, &args);
+
+    let report = a.profile_report().unwrap();
+    assert!(report.num_failed_derivations() <= report.num_derivations());
+    assert_eq!(
+        report.num_derivations() - report.num_failed_derivations(),
+        prakriyas.len()
+    );
+}
+
+#[test]
+fn test_num_failed_derivations_without_profile() {
+    // An empty upadesha is invalid, so this derivation always fails.
+    let dhatu = Dhatu::new("", Gana::Bhvadi);
+    let args = TinantaArgs::builder()
+        .lakara(Lakara::Lat)
+        .prayoga(Prayoga::Kartari)
+        .purusha(Purusha::Prathama)
+        .vacana(Vacana::Eka)
+        .build()
+        .unwrap();
+
+    let a = Ashtadhyayi::new();
+    assert_eq!(a.num_failed_derivations(), 0);
+    assert!(a.derive_tinantas(&dhatu, &args).is_empty());
+    assert_eq!(a.num_failed_derivations(), 1);
+
+    // Successful derivations don't change the count.
+    derive_bhavati(&a);
+    assert_eq!(a.num_failed_derivations(), 1);
+
+    let profiled = Ashtadhyayi::builder().profile(true).build();
+    profiled.derive_tinantas(&dhatu, &args);
+    let report = profiled.profile_report().unwrap();
+    assert_eq!(
+        profiled.num_failed_derivations(),
+        report.num_failed_derivations()
+    );
+}
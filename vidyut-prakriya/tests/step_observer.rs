@@ -0,0 +1,119 @@
+use std::ops::ControlFlow;
+use std::sync::{Arc, Mutex};
+use vidyut_prakriya::args::*;
+use vidyut_prakriya::{Ashtadhyayi, AshtadhyayiBuilder, Prakriya, Rule};
+
+fn bhavati_args() -> (Dhatu, TinantaArgs) {
+    let dhatu = Dhatu::new("BU", Gana::Bhvadi);
+    let args = TinantaArgs::builder()
+        .lakara(Lakara::Lat)
+        .prayoga(Prayoga::Kartari)
+        .purusha(Purusha::Prathama)
+        .vacana(Vacana::Eka)
+        .build()
+        .unwrap();
+    (dhatu, args)
+}
+
+#[test]
+fn test_observed_types_are_send_and_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Ashtadhyayi>();
+    assert_send_sync::<AshtadhyayiBuilder>();
+    assert_send_sync::<Prakriya>();
+}
+
+#[test]
+fn test_step_observer_sees_every_logged_step() {
+    let seen: Arc<Mutex<Vec<Rule>>> = Arc::new(Mutex::new(Vec::new()));
+    let seen_clone = seen.clone();
+    let a = Ashtadhyayi::builder()
+        .step_observer(move |_, rule| {
+            seen_clone.lock().unwrap().push(rule);
+            ControlFlow::Continue(())
+        })
+        .build();
+
+    let (dhatu, args) = bhavati_args();
+    let prakriyas = a.derive_tinantas(&dhatu, &args);
+    assert!(!prakriyas.is_empty());
+
+    let seen = seen.lock().unwrap();
+    for p in &prakriyas {
+        for step in p.history() {
+            assert!(seen.contains(&step.rule()));
+        }
+    }
+    let num_logged: usize = prakriyas.iter().map(|p| p.history().len()).sum();
+    assert!(seen.len() >= num_logged);
+}
+
+#[test]
+fn test_step_observer_without_log_steps() {
+    let texts: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+    let texts_clone = texts.clone();
+    let a = Ashtadhyayi::builder()
+        .log_steps(false)
+        .step_observer(move |p, _| {
+            texts_clone.lock().unwrap().push(p.text().to_string());
+            ControlFlow::Continue(())
+        })
+        .build();
+
+    let (dhatu, args) = bhavati_args();
+    let prakriyas = a.derive_tinantas(&dhatu, &args);
+    assert!(prakriyas.iter().all(|p| p.history().is_empty()));
+
+    // The observer sees the state just after each rule, so the last state it sees is the final
+    // result.
+    let texts = texts.lock().unwrap();
+    assert_eq!(texts.last().map(|s| s.as_str()), Some("Bavati"));
+}
+
+#[test]
+fn test_step_observer_sees_logged_history() {
+    let is_logged = Arc::new(Mutex::new(true));
+    let is_logged_clone = is_logged.clone();
+    let a = Ashtadhyayi::builder()
+        .step_observer(move |p, rule| {
+            let last = p.history().last().map(|s| s.rule());
+            *is_logged_clone.lock().unwrap() &= last == Some(rule);
+            ControlFlow::Continue(())
+        })
+        .build();
+
+    let (dhatu, args) = bhavati_args();
+    a.derive_tinantas(&dhatu, &args);
+    assert!(*is_logged.lock().unwrap());
+}
+
+#[test]
+fn test_step_observer_can_stop_derivation() {
+    let (dhatu, args) = bhavati_args();
+    let num_steps = |max_steps: Option<usize>| {
+        let count = Arc::new(Mutex::new(0));
+        let count_clone = count.clone();
+        let a = Ashtadhyayi::builder()
+            .step_observer(move |_, _| {
+                let mut count = count_clone.lock().unwrap();
+                *count += 1;
+                match max_steps {
+                    Some(n) if *count >= n => ControlFlow::Break(()),
+                    _ => ControlFlow::Continue(()),
+                }
+            })
+            .build();
+        let prakriyas = a.derive_tinantas(&dhatu, &args);
+        let count = *count.lock().unwrap();
+        (prakriyas, count)
+    };
+
+    let (all, total) = num_steps(None);
+    assert!(!all.is_empty());
+
+    // Stopping on the first step discards the prakriya and skips all other paths.
+    let (stopped, count) = num_steps(Some(1));
+    assert!(stopped.is_empty());
+    assert_eq!(count, 1);
+    assert!(count < total);
+}